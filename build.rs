@@ -3,6 +3,8 @@ fn main() {
   tonic_build::configure()
     .out_dir("src/pb")
     .protoc_arg("--experimental_allow_proto3_optional")
+    // keeps the keys of JSON objects sorted so that the same value is always encoded the same way
+    .btree_map(&[".sui.checkpoint.v1.ValueMap.map"])
     .compile(
       &[
         "proto/checkpoint.proto",
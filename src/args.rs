@@ -1,11 +1,31 @@
 use simple_home_dir::*;
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 
 fn sui_config_path() -> String {
   format!("{}/.sf_sui/sui_config/full_node.yaml", home_dir().unwrap().display().to_string())
 }
 
-#[derive(Default, Debug, Parser)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+  /// One FIRE line per record i.e. BLOCK_START, CHECKPOINT, TRX, OBJ_CHANGE, EVT, DSP_UPDATE and BLOCK_END
+  #[default]
+  Lines,
+  /// A single self-contained FIRE BLOCK line per checkpoint carrying a base64 encoded CheckpointData
+  Block,
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum RpcApi {
+  /// Use the REST API if the endpoints answer it and fall back to JSON-RPC otherwise
+  #[default]
+  Auto,
+  /// The experimental REST API which is the fastest but needs `enable-experimental-rest-api: true` on the node
+  Rest,
+  /// The standard JSON-RPC API that every node and provider exposes. It takes a few requests per checkpoint
+  JsonRpc,
+}
+
+#[derive(Debug, Parser)]
 pub struct Args {
   /// The fullnode config file
   #[arg(short = 'c', long, default_value_t = sui_config_path())]
@@ -15,12 +35,137 @@ pub struct Args {
   #[arg(short = 'i', long, default_value = "4btiuiMPvEENsttpZC7CZ53DruC3MAgfznDbASZ7DR6S")]
   pub chain_id: String,
 
-  /// Which checkpoint should we start streaming data from
-  #[arg(short = 's', long, default_value_t = 1)]
-  pub starting_checkpoint_seq: u64,
+  /// Which checkpoint should we start streaming data from. If not provided, the stream resumes right after
+  /// the checkpoint stored in the cursor file or starts from 1 if there is none
+  #[arg(short = 's', long)]
+  pub starting_checkpoint_seq: Option<u64>,
+
+  /// Last checkpoint to stream. Once it has been emitted the process exits with status 0. If not provided,
+  /// the stream runs forever
+  #[arg(short = 'e', long)]
+  pub stop_checkpoint: Option<u64>,
 
   /// You can use https://fullnode.mainnet.sui.io:443 for mainnet
-  /// Note that if one is not provided, a local sui-node will be spinned up instead
-  #[arg(short = 'r', long)]
-  pub rpc_client_url: Option<String>,
+  /// Can be repeated or comma separated. Checkpoints are fetched from the healthiest endpoint and fail over to
+  /// the others when it is slow or down.
+  /// Note that if none is provided, a local sui-node will be spinned up instead
+  #[arg(short = 'r', long = "rpc-client-url", value_delimiter = ',')]
+  pub rpc_client_urls: Vec<String>,
+
+  /// Which API of the rpc endpoints checkpoints are fetched through. The choice is made once at startup
+  #[arg(long, value_enum, default_value_t = RpcApi::Auto)]
+  pub rpc_api: RpcApi,
+
+  /// Read checkpoints from somewhere else than the rpc endpoints. dir://<path> reads the BCS encoded <seq>.chk files
+  /// of a checkpoint archive or of the node's checkpoint exporter and keeps watching the directory for new files.
+  /// No sui-node is started and --rpc-client-url is ignored when set
+  #[arg(long)]
+  pub source: Option<String>,
+
+  /// Write every checkpoint to this directory as a BCS encoded <seq>.chk file before it is processed. The
+  /// recording can be fed back with --replay
+  #[arg(long)]
+  pub record: Option<String>,
+
+  /// Feed the checkpoints recorded with --record back through the checkpoint handler and the converters without
+  /// any network. The stream stops at the last of the consecutive recorded checkpoints or at --stop-checkpoint,
+  /// whichever comes first
+  #[arg(long, conflicts_with = "source")]
+  pub replay: Option<String>,
+
+  /// How checkpoints are written to the output
+  #[arg(short = 'o', long, value_enum, default_value_t = OutputFormat::Lines)]
+  pub output_format: OutputFormat,
+
+  /// File where the last fully emitted checkpoint is persisted after every block
+  #[arg(long)]
+  pub cursor_file: Option<String>,
+
+  /// How many seconds to wait for a missing checkpoint to be refetched before giving up
+  #[arg(long, default_value_t = 60)]
+  pub gap_timeout_secs: u64,
+
+  /// Max number of checkpoints that arrived early and are held back until the missing ones are refetched
+  #[arg(long, default_value_t = 100)]
+  pub reorder_buffer_size: usize,
+
+  /// Number of parallel fetcher and handler pipelines used to backfill the range up to --stop-checkpoint
+  #[arg(long, default_value_t = 1)]
+  pub backfill_workers: usize,
+
+  /// Max number of checkpoints the backfill workers can fetch ahead of the next checkpoint to be emitted.
+  /// This bounds the memory used to put the checkpoints back in order
+  #[arg(long, default_value_t = 1000)]
+  pub backfill_window: usize,
+
+  /// How many seconds to wait on SIGINT/SIGTERM for in-flight checkpoints to be emitted and the sui-node to stop
+  /// before exiting with an error
+  #[arg(long, default_value_t = 30)]
+  pub shutdown_timeout_secs: u64,
+
+  /// How many times in a row the fetcher and handler tasks can fail before the streamer gives up. They are
+  /// restarted from the last emitted checkpoint with an exponential backoff in between.
+  #[arg(long, default_value_t = 5)]
+  pub max_consecutive_failures: u32,
+
+  /// Max number of requests sent to the rpc endpoints at the same time
+  #[arg(long, default_value_t = 10)]
+  pub max_in_flight_requests: usize,
+
+  /// Max number of requests per second sent to the rpc endpoints. Not limited if not provided
+  #[arg(long)]
+  pub max_requests_per_sec: Option<u32>,
+
+  /// Capacity of the queue between each fetcher and its checkpoint handler
+  #[arg(long, default_value_t = 1000)]
+  pub download_queue_size: usize,
+
+  /// Capacity of the queue between the checkpoint handlers and the output
+  #[arg(long, default_value_t = 1000)]
+  pub checkpoint_queue_size: usize,
+
+  /// Max number of checkpoints a checkpoint handler processes at once
+  #[arg(long, default_value_t = 25)]
+  pub batch_size: usize,
+
+  /// Let the batch size grow up to 16 times --batch-size while catching up and shrink down to 1 at the chain tip
+  #[arg(long)]
+  pub adaptive_batch_size: bool,
+
+  /// Directory where the Move packages seen in the stream are cached so that their types can still be resolved
+  /// after a restart. Packages are only kept in memory if not provided
+  #[arg(long)]
+  pub module_cache_dir: Option<String>,
+
+  /// Decode the BCS contents of every event using the layout of its type and attach the result next to the raw
+  /// bytes. Events whose layout can't be resolved are flagged with the reason
+  #[arg(long)]
+  pub decode_events: bool,
+
+  /// Infer the Move type of the pure inputs of programmable transactions from the functions they are passed to
+  /// and attach the decoded value next to the raw bytes
+  #[arg(long)]
+  pub decode_pure_inputs: bool,
+
+  /// Decode the BCS contents of the changed Move objects using the layout of their type and attach the result
+  /// next to the raw bytes
+  #[arg(long)]
+  pub decode_objects: bool,
+
+  /// Only decode objects whose type matches one of these patterns. A pattern is a package (0x2), a module
+  /// (0x2::coin) or a struct regardless of its type parameters (0x2::coin::Coin). Can be repeated or comma separated
+  #[arg(long, value_delimiter = ',')]
+  pub decode_objects_allow: Vec<String>,
+
+  /// Never decode objects whose type matches one of these patterns, e.g. heavy types that aren't needed. Takes
+  /// precedence over --decode-objects-allow
+  #[arg(long, value_delimiter = ',')]
+  pub decode_objects_deny: Vec<String>,
+}
+
+/// Same values as running the binary without any arguments
+impl Default for Args {
+  fn default() -> Self {
+    Self::parse_from([env!("CARGO_PKG_NAME")])
+  }
 }
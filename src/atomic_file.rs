@@ -0,0 +1,49 @@
+use std::{fs::{self, File}, io::Write, path::{Path, PathBuf}};
+use eyre::{Result, WrapErr};
+
+/// Writes the content to a temporary file next to the target, syncs it and renames it over the target so that a
+/// crash half way through never leaves a truncated file behind. The directory is synced as well since the rename
+/// is only durable once the directory entry is.
+pub fn write_atomically(path: &Path, content: &[u8]) -> Result<()> {
+  let tmp_path = tmp_path(path);
+
+  let mut file = File::create(&tmp_path).wrap_err_with(|| format!("could not create {}", tmp_path.display()))?;
+  file.write_all(content).wrap_err_with(|| format!("could not write {}", tmp_path.display()))?;
+  file.sync_all().wrap_err_with(|| format!("could not sync {}", tmp_path.display()))?;
+  fs::rename(&tmp_path, path).wrap_err_with(|| format!("could not write {}", path.display()))?;
+
+  let dir = parent_dir(path);
+  File::open(dir)
+  .and_then(|dir| dir.sync_all())
+  .wrap_err_with(|| format!("could not sync {}", dir.display()))?;
+
+  Ok(())
+}
+
+/// A bare file name lives in the current directory
+fn parent_dir(path: &Path) -> &Path {
+  match path.parent() {
+    Some(parent) if !parent.as_os_str().is_empty() => parent,
+    _ => Path::new("."),
+  }
+}
+
+fn tmp_path(path: &Path) -> PathBuf {
+  let mut tmp_path = path.to_path_buf().into_os_string();
+  tmp_path.push(".tmp");
+
+  tmp_path.into()
+}
+
+#[cfg(test)]
+mod tests {
+  use std::path::Path;
+  use super::parent_dir;
+
+  #[test]
+  fn finds_the_directory_to_sync() {
+    assert_eq!(parent_dir(Path::new("/data/cursor.json")), Path::new("/data"));
+    assert_eq!(parent_dir(Path::new("data/cursor.json")), Path::new("data"));
+    assert_eq!(parent_dir(Path::new("cursor.json")), Path::new("."));
+  }
+}
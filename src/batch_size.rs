@@ -0,0 +1,69 @@
+/// Upper bound of an adaptive batch relative to the configured batch size
+const ADAPTIVE_MAX_FACTOR: usize = 16;
+
+/// How many checkpoints the checkpoint handler processes at once. A fixed size never changes. An adaptive one
+/// doubles while full batches keep coming in, i.e. while catching up, and halves once they thin out, i.e. at the
+/// chain tip.
+#[derive(Debug, Clone, Copy)]
+pub struct BatchSize {
+  current: usize,
+  min: usize,
+  max: usize,
+}
+
+impl BatchSize {
+  pub fn fixed(size: usize) -> Self {
+    let size = size.max(1);
+
+    Self {current: size, min: size, max: size}
+  }
+
+  pub fn adaptive(size: usize) -> Self {
+    let size = size.max(1);
+
+    Self {current: size, min: 1, max: size * ADAPTIVE_MAX_FACTOR}
+  }
+
+  pub fn current(&self) -> usize {
+    self.current
+  }
+
+  /// Called with the length of the batch that was just processed
+  pub fn update(&mut self, batch_len: usize) {
+    if batch_len >= self.current {
+      self.current = (self.current * 2).min(self.max);
+    } else if batch_len <= self.current / 2 {
+      self.current = (self.current / 2).max(self.min);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::BatchSize;
+
+  #[test]
+  fn fixed_size_never_changes() {
+    let mut batch_size = BatchSize::fixed(25);
+
+    batch_size.update(25);
+    batch_size.update(1);
+    assert_eq!(batch_size.current(), 25);
+  }
+
+  #[test]
+  fn adaptive_size_grows_while_catching_up_and_shrinks_at_the_tip() {
+    let mut batch_size = BatchSize::adaptive(4);
+
+    for _ in 0..10 {
+      let full_batch = batch_size.current();
+      batch_size.update(full_batch);
+    }
+    assert_eq!(batch_size.current(), 64);
+
+    for _ in 0..10 {
+      batch_size.update(1);
+    }
+    assert_eq!(batch_size.current(), 1);
+  }
+}
@@ -0,0 +1,282 @@
+use std::{ffi::OsStr, fs, io::ErrorKind, path::PathBuf, sync::{Arc, Mutex, OnceLock}, time::Duration};
+use async_trait::async_trait;
+use eyre::{bail, ensure, eyre, Result, WrapErr};
+use log::warn;
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use sui_indexer::framework::fetcher::CheckpointDownloadData;
+use sui_types::full_checkpoint_content::CheckpointData;
+use tokio::{sync::Notify, time::timeout};
+use crate::{
+  atomic_file::write_atomically, checkpoint_source::{CheckpointSource, FetchError, POLL_INTERVAL},
+};
+
+const DIR_SOURCE_SCHEME: &str = "dir://";
+const CHECKPOINT_FILE_EXTENSION: &str = "chk";
+/// First byte of a checkpoint file. It tells how the rest of the file is encoded and BCS is the only encoding
+/// used by the archives and the checkpoint exporter.
+const BCS_BLOB_ENCODING: u8 = 1;
+/// The directory is still polled this often while it is watched in case the file system doesn't report every
+/// change, e.g. some network mounts
+const WATCH_FALLBACK_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A directory of `<seq>.chk` files such as an unpacked checkpoint archive, the directory the node exports
+/// its checkpoints to or a recording of a previous run. Clones share what is known about the latest checkpoint.
+#[derive(Debug, Clone)]
+pub struct CheckpointDir {
+  path: PathBuf,
+  /// Highest checkpoint found so far. None until the directory has been scanned once.
+  latest: Arc<Mutex<Option<u64>>>,
+  /// Started the first time the tail fetcher waits for new files. None if the directory can't be watched.
+  watcher: Arc<OnceLock<Option<Mutex<RecommendedWatcher>>>>,
+  /// Woken up by the watcher whenever a file is created or renamed in the directory
+  changes: Arc<Notify>,
+}
+
+impl CheckpointDir {
+  /// Parses a `dir://<path>` source
+  pub fn from_source(source: &str) -> Result<Self> {
+    let Some(path) = source.strip_prefix(DIR_SOURCE_SCHEME) else {
+      bail!("unsupported checkpoint source {}, expected {}<path>", source, DIR_SOURCE_SCHEME)
+    };
+    ensure!(!path.is_empty(), "checkpoint source {} is missing a path", source);
+
+    Ok(Self::new(PathBuf::from(path)))
+  }
+
+  pub fn new(path: PathBuf) -> Self {
+    Self {path, latest: Arc::default(), watcher: Arc::default(), changes: Arc::default()}
+  }
+
+  /// Same as `new` but creates the directory if needed so that checkpoints can be written to it
+  pub fn create(path: PathBuf) -> Result<Self> {
+    fs::create_dir_all(&path)
+    .wrap_err_with(|| format!("could not create checkpoint directory {}", path.display()))?;
+
+    Ok(Self::new(path))
+  }
+
+  fn checkpoint_path(&self, seq: u64) -> PathBuf {
+    self.path.join(format!("{}.{}", seq, CHECKPOINT_FILE_EXTENSION))
+  }
+
+  /// Returns None if the file of the checkpoint has not been written yet
+  pub fn read_checkpoint(&self, seq: u64) -> Result<Option<CheckpointDownloadData>> {
+    let path = self.checkpoint_path(seq);
+    let bytes = match fs::read(&path) {
+      Ok(bytes) => bytes,
+      Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+      Err(err) => return Err(err).wrap_err_with(|| format!("could not read {}", path.display())),
+    };
+
+    let data = decode_checkpoint_file(&bytes)
+    .wrap_err_with(|| format!("could not decode {}", path.display()))?;
+    ensure!(
+      data.checkpoint_summary.sequence_number == seq,
+      "{} contains checkpoint {}",
+      path.display(),
+      data.checkpoint_summary.sequence_number,
+    );
+
+    // Same as the size of a downloaded checkpoint i.e. without the encoding byte
+    Ok(Some(CheckpointDownloadData {size: bytes.len() - 1, data}))
+  }
+
+  /// Writes the checkpoint in the same format it is read in
+  pub fn write_checkpoint(&self, checkpoint_download_data: &CheckpointDownloadData) -> Result<()> {
+    let path = self.checkpoint_path(checkpoint_download_data.data.checkpoint_summary.sequence_number);
+
+    let mut bytes = vec![BCS_BLOB_ENCODING];
+    bytes.extend(bcs::to_bytes(&checkpoint_download_data.data).map_err(|err| eyre!("{}", err))?);
+
+    write_atomically(&path, &bytes)
+  }
+
+  /// The last checkpoint of the run of consecutive checkpoint files that starts at the given checkpoint. None if
+  /// there is no file for the given checkpoint.
+  pub fn last_consecutive_checkpoint(&self, from: u64) -> Option<u64> {
+    let mut last = None;
+    let mut seq = from;
+
+    while self.checkpoint_path(seq).is_file() {
+      last = Some(seq);
+      seq += 1;
+    }
+
+    last
+  }
+
+  /// Highest checkpoint of all the files in the directory
+  fn scan_latest_checkpoint(&self) -> Result<u64> {
+    let mut latest = None;
+
+    for entry in fs::read_dir(&self.path).wrap_err_with(|| format!("could not read {}", self.path.display()))? {
+      let path = entry?.path();
+      if path.extension() != Some(OsStr::new(CHECKPOINT_FILE_EXTENSION)) {
+        continue
+      }
+
+      if let Some(seq) = path.file_stem().and_then(OsStr::to_str).and_then(|s| s.parse::<u64>().ok()) {
+        latest = latest.max(Some(seq));
+      }
+    }
+
+    latest.ok_or_else(|| eyre!("there are no checkpoint files in {}", self.path.display()))
+  }
+
+  fn watch(&self) -> Result<RecommendedWatcher> {
+    let changes = self.changes.clone();
+    // A checkpoint written atomically shows up as a rename of its temporary file. A permit is stored if nobody is
+    // waiting yet so a file written in between two waits is not missed.
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+      if event.map_or(true, |event| event.kind.is_create() || event.kind.is_modify()) {
+        changes.notify_one();
+      }
+    })?;
+    watcher.watch(&self.path, RecursiveMode::NonRecursive)
+    .wrap_err_with(|| format!("could not watch {}", self.path.display()))?;
+
+    Ok(watcher)
+  }
+}
+
+fn decode_checkpoint_file(bytes: &[u8]) -> Result<CheckpointData> {
+  let Some((encoding, content)) = bytes.split_first() else {
+    bail!("empty checkpoint file")
+  };
+  ensure!(*encoding == BCS_BLOB_ENCODING, "unsupported checkpoint file encoding {}", encoding);
+
+  bcs::from_bytes(content).map_err(|err| eyre!("{}", err))
+}
+
+/// Files are picked up as soon as they are written which allows following a directory that is still being
+/// written to. The directory is watched for new files and only polled as a fallback.
+#[async_trait]
+impl CheckpointSource for CheckpointDir {
+  /// The directory is scanned only once. After that new files are expected to show up in order, as the node and
+  /// the recorder write them, so checking for the file of the next checkpoint is enough.
+  async fn latest_checkpoint(&self) -> Result<u64> {
+    let mut latest = self.latest.lock().unwrap();
+    let mut seq = match *latest {
+      Some(seq) => seq,
+      None => self.scan_latest_checkpoint()?,
+    };
+
+    while self.checkpoint_path(seq + 1).is_file() {
+      seq += 1;
+    }
+    *latest = Some(seq);
+
+    Ok(seq)
+  }
+
+  /// A file that can't be read or decoded won't get any better by retrying. Neither will a missing file once a later
+  /// checkpoint has been written since the directory is filled in order.
+  async fn fetch_checkpoint(&self, seq: u64) -> Result<CheckpointData, FetchError> {
+    match self.read_checkpoint(seq) {
+      Ok(Some(checkpoint_download_data)) => Ok(checkpoint_download_data.data),
+      Ok(None) if self.latest_checkpoint().await.map_or(false, |latest| seq < latest) => Err(FetchError::Permanent(
+        eyre!("checkpoint {} is missing from {}", seq, self.path.display()),
+      )),
+      Ok(None) => Err(FetchError::Transient(
+        eyre!("checkpoint {} has not been written to {} yet", seq, self.path.display()),
+      )),
+      Err(err) => Err(FetchError::Permanent(err)),
+    }
+  }
+
+  async fn wait_for_new_checkpoints(&self) {
+    let watcher = self.watcher.get_or_init(|| match self.watch() {
+      Ok(watcher) => Some(Mutex::new(watcher)),
+      Err(err) => {
+        warn!("Polling {} for new checkpoints since it can't be watched: {:?}", self.path.display(), err);
+        None
+      },
+    });
+    let poll_interval = if watcher.is_some() {WATCH_FALLBACK_POLL_INTERVAL} else {POLL_INTERVAL};
+
+    let _ = timeout(poll_interval, self.changes.notified()).await;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{fs, path::PathBuf, time::Duration};
+  use tokio::time::timeout;
+  use crate::test_utils::TempDir;
+  use super::{CheckpointDir, CheckpointSource, BCS_BLOB_ENCODING};
+
+  #[test]
+  fn parses_dir_sources() {
+    assert_eq!(CheckpointDir::from_source("dir:///data/checkpoints").unwrap().path, PathBuf::from("/data/checkpoints"));
+    assert!(CheckpointDir::from_source("dir://").is_err());
+    assert!(CheckpointDir::from_source("/data/checkpoints").is_err());
+    assert!(CheckpointDir::from_source("s3://bucket/checkpoints").is_err());
+  }
+
+  #[test]
+  fn finds_the_end_of_a_recording() {
+    let temp_dir = TempDir::new("checkpoint-recording");
+    let path = temp_dir.path();
+    let dir = CheckpointDir::create(path.to_path_buf()).unwrap();
+
+    for seq in [3, 4, 5, 7] {
+      fs::write(path.join(format!("{}.chk", seq)), [BCS_BLOB_ENCODING]).unwrap();
+    }
+
+    assert_eq!(dir.last_consecutive_checkpoint(3), Some(5));
+    assert_eq!(dir.last_consecutive_checkpoint(5), Some(5));
+    assert_eq!(dir.last_consecutive_checkpoint(6), None);
+    assert_eq!(dir.last_consecutive_checkpoint(7), Some(7));
+  }
+
+  #[test]
+  fn skips_missing_files_and_rejects_invalid_ones() {
+    let temp_dir = TempDir::new("checkpoint-dir");
+    let path = temp_dir.path();
+    let dir = CheckpointDir::new(path.to_path_buf());
+
+    assert!(dir.read_checkpoint(1).unwrap().is_none());
+
+    fs::write(path.join("1.chk"), [BCS_BLOB_ENCODING, 1, 2, 3]).unwrap();
+    assert!(dir.read_checkpoint(1).is_err());
+
+    fs::write(path.join("2.chk"), [0, 1, 2, 3]).unwrap();
+    assert!(dir.read_checkpoint(2).is_err());
+
+    fs::write(path.join("3.chk"), []).unwrap();
+    assert!(dir.read_checkpoint(3).is_err());
+  }
+
+  #[tokio::test]
+  async fn follows_new_files_after_the_first_scan() {
+    let temp_dir = TempDir::new("checkpoint-latest");
+    let path = temp_dir.path();
+    let dir = CheckpointDir::create(path.to_path_buf()).unwrap();
+
+    assert!(dir.latest_checkpoint().await.is_err());
+
+    for seq in [5, 3] {
+      fs::write(path.join(format!("{}.chk", seq)), [BCS_BLOB_ENCODING]).unwrap();
+    }
+    assert_eq!(dir.latest_checkpoint().await.unwrap(), 5);
+
+    for seq in [6, 7] {
+      fs::write(path.join(format!("{}.chk", seq)), [BCS_BLOB_ENCODING]).unwrap();
+    }
+    assert_eq!(dir.latest_checkpoint().await.unwrap(), 7);
+  }
+
+  #[tokio::test]
+  async fn wakes_up_as_soon_as_a_file_is_written() {
+    let temp_dir = TempDir::new("checkpoint-watch");
+    let dir = CheckpointDir::new(temp_dir.path().to_path_buf());
+
+    // Nothing has been written so the first wait only starts watching
+    assert!(timeout(Duration::from_millis(100), dir.wait_for_new_checkpoints()).await.is_err());
+    assert!(dir.watcher.get().unwrap().is_some());
+
+    fs::write(temp_dir.path().join("1.chk"), [BCS_BLOB_ENCODING]).unwrap();
+    // Well before the fallback poll interval
+    assert!(timeout(Duration::from_secs(1), dir.wait_for_new_checkpoints()).await.is_ok());
+  }
+}
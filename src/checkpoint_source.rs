@@ -0,0 +1,82 @@
+use std::{collections::BTreeMap, fmt, time::Duration};
+use async_trait::async_trait;
+use eyre::{eyre, Report, Result};
+use sui_types::full_checkpoint_content::CheckpointData;
+use tokio::time::sleep;
+
+/// How often a source that can't tell when new checkpoints show up is asked for the latest checkpoint
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Where the streamer gets its checkpoints from. The fetchers take care of fetching ranges in order, retrying and
+/// following the tip of the chain so an implementation only needs to know how to get a single checkpoint.
+#[async_trait]
+pub trait CheckpointSource: Send + Sync {
+  /// Sequence number of the most recent checkpoint that can be fetched
+  async fn latest_checkpoint(&self) -> Result<u64>;
+
+  /// Transient failures are retried, including checkpoints that are not available yet
+  async fn fetch_checkpoint(&self, seq: u64) -> Result<CheckpointData, FetchError>;
+
+  /// How many checkpoints can be fetched at the same time
+  fn max_concurrent_fetches(&self) -> usize {
+    1
+  }
+
+  /// Returns once checkpoints after the latest one might be available. Sources that can't be notified are polled.
+  async fn wait_for_new_checkpoints(&self) {
+    sleep(POLL_INTERVAL).await
+  }
+}
+
+/// Why a checkpoint could not be fetched. Any eyre error converts into a transient one so that `?` keeps retrying
+/// by default.
+#[derive(Debug)]
+pub enum FetchError {
+  /// Might succeed later on e.g. the endpoint is down or the checkpoint has not been produced yet
+  Transient(Report),
+  /// Can never succeed e.g. a corrupt checkpoint file, so the fetchers give up on the checkpoint right away
+  Permanent(Report),
+}
+
+impl From<Report> for FetchError {
+  fn from(err: Report) -> Self {
+    Self::Transient(err)
+  }
+}
+
+impl fmt::Display for FetchError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Transient(err) | Self::Permanent(err) => write!(f, "{:?}", err),
+    }
+  }
+}
+
+impl std::error::Error for FetchError {}
+
+/// Serves a fixed set of checkpoints that are already in memory, e.g. fixtures in tests
+pub struct InMemorySource {
+  checkpoints: BTreeMap<u64, CheckpointData>,
+}
+
+impl InMemorySource {
+  pub fn new(checkpoints: impl IntoIterator<Item = CheckpointData>) -> Self {
+    let checkpoints = checkpoints.into_iter()
+    .map(|checkpoint| (checkpoint.checkpoint_summary.sequence_number, checkpoint))
+    .collect();
+
+    Self {checkpoints}
+  }
+}
+
+#[async_trait]
+impl CheckpointSource for InMemorySource {
+  async fn latest_checkpoint(&self) -> Result<u64> {
+    self.checkpoints.keys().last().copied().ok_or_else(|| eyre!("there are no checkpoints"))
+  }
+
+  /// Nothing is ever added so a missing checkpoint stays missing
+  async fn fetch_checkpoint(&self, seq: u64) -> Result<CheckpointData, FetchError> {
+    self.checkpoints.get(&seq).cloned().ok_or_else(|| FetchError::Permanent(eyre!("checkpoint {} not found", seq)))
+  }
+}
@@ -0,0 +1,132 @@
+use sui_indexer::handlers::CheckpointDataToCommit;
+use crate::{pb::sui::checkpoint::{self as pb}, sui::move_decoder::MoveDecoder};
+use super::{
+  checkpoint::convert_checkpoint, display_update::convert_display_update, epoch::convert_epoch,
+  package::convert_indexed_packages,
+  sui_event::{convert_indexed_event, decode_indexed_event},
+  tx::{convert_transaction, decode_pure_inputs, decode_transaction_events}, tx_index::convert_tx_index,
+  tx_object_change::convert_tx_object_changes,
+};
+
+/// Builds the self-contained envelope that is emitted as the payload of a single FIRE BLOCK line
+pub fn convert_checkpoint_data(source: &CheckpointDataToCommit) -> pb::CheckpointData {
+  pb::CheckpointData {
+    checkpoint: Some(convert_checkpoint(&source.checkpoint)),
+    transactions: source.transactions.iter().map(convert_transaction).collect::<Vec<_>>(),
+    events: source.events.iter().map(convert_indexed_event).collect::<Vec<_>>(),
+    object_change: Some(convert_tx_object_changes(&source.object_changes)),
+    tx_indices: source.tx_indices.iter().map(convert_tx_index).collect::<Vec<_>>(),
+    display_updates: source.display_updates.values().map(convert_display_update).collect::<Vec<_>>(),
+    packages: convert_indexed_packages(&source.packages),
+    epoch: source.epoch.as_ref().map(convert_epoch),
+  }
+}
+
+/// Attaches the inferred types and decoded values to the pure inputs of the transactions of an already converted
+/// envelope
+pub fn decode_checkpoint_data_pure_inputs(
+  decoder: &MoveDecoder,
+  source: &CheckpointDataToCommit,
+  target: &mut pb::CheckpointData,
+) {
+  for (tx, pb_tx) in source.transactions.iter().zip(target.transactions.iter_mut()) {
+    decode_pure_inputs(decoder, tx, pb_tx);
+  }
+}
+
+/// Attaches the decoded contents to the events of an already converted envelope, both the standalone ones and
+/// the ones nested in the transactions
+pub fn decode_checkpoint_data_events(
+  decoder: &MoveDecoder,
+  source: &CheckpointDataToCommit,
+  target: &mut pb::CheckpointData,
+) {
+  for (tx, pb_tx) in source.transactions.iter().zip(target.transactions.iter_mut()) {
+    decode_transaction_events(decoder, tx, pb_tx);
+  }
+
+  for (event, pb_event) in source.events.iter().zip(target.events.iter_mut()) {
+    pb_event.decoded = Some(decode_indexed_event(decoder, event));
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{collections::BTreeMap, str::FromStr};
+  use sui_indexer::{
+    handlers::{CheckpointDataToCommit, TransactionObjectChangesToCommit}, models::display::StoredDisplay,
+    types::IndexedCheckpoint,
+  };
+  use sui_types::{crypto::AggregateAuthoritySignature, digests::CheckpointDigest};
+  use crate::pb::sui::checkpoint::{self as pb};
+  use super::convert_checkpoint_data;
+
+  #[test]
+  fn converts_checkpoint_data() {
+    let checkpoint = IndexedCheckpoint {
+      sequence_number: 1448000,
+      checkpoint_digest: CheckpointDigest::from_str("8Y1Kx4BJxbAq2WvJcHmrbgq9VewnZ6um6biCDeb7XbCR").unwrap(),
+      epoch: 19,
+      tx_digests: vec![],
+      network_total_transactions: 1449228,
+      previous_checkpoint_digest: Some(
+        CheckpointDigest::from_str("GuvEqJeH5uzfxpXk4js5HXe3NMSr7yfx8Xc4At4wXrKR").unwrap(),
+      ),
+      timestamp_ms: 1682990756147,
+      total_gas_cost: 0,
+      computation_cost: 0,
+      storage_cost: 0,
+      storage_rebate: 0,
+      non_refundable_storage_fee: 0,
+      checkpoint_commitments: vec![],
+      validator_signature: AggregateAuthoritySignature::default(),
+      successful_tx_num: 0,
+      end_of_epoch_data: None,
+      end_of_epoch: false,
+    };
+
+    let mut display_updates = BTreeMap::new();
+    display_updates.insert("some_object_type".to_string(), StoredDisplay {
+      object_type: "some_object_type".to_string(),
+      id: vec![1, 2, 3],
+      version: 1,
+      bcs: vec![4, 5, 6],
+    });
+
+    let source = CheckpointDataToCommit {
+      checkpoint,
+      transactions: vec![],
+      events: vec![],
+      object_changes: TransactionObjectChangesToCommit {
+        changed_objects: vec![],
+        deleted_objects: vec![],
+      },
+      object_history_changes: TransactionObjectChangesToCommit {
+        changed_objects: vec![],
+        deleted_objects: vec![],
+      },
+      packages: vec![],
+      tx_indices: vec![],
+      display_updates,
+      epoch: None,
+    };
+
+    let pb_checkpoint_data = convert_checkpoint_data(&source);
+
+    assert_eq!(pb_checkpoint_data.checkpoint.unwrap().sequence_number, 1448000);
+    assert!(pb_checkpoint_data.transactions.is_empty());
+    assert!(pb_checkpoint_data.tx_indices.is_empty());
+    assert!(pb_checkpoint_data.packages.is_empty());
+    assert!(pb_checkpoint_data.epoch.is_none());
+    assert_eq!(pb_checkpoint_data.object_change, Some(pb::TransactionObjectChange {
+      changed_objects: vec![],
+      deleted_objects: vec![],
+    }));
+    assert_eq!(pb_checkpoint_data.display_updates, vec![pb::StoredDisplay {
+      object_type: "some_object_type".to_string(),
+      id: vec![1, 2, 3],
+      version: 1,
+      bcs: vec![4, 5, 6],
+    }]);
+  }
+}
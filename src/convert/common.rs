@@ -5,7 +5,7 @@ use serde_json::Value;
 use sui_types::{
   base_types::{AuthorityName, MoveObjectType, ObjectID, ObjectRef, SuiAddress, SequenceNumber},
   committee::StakeUnit, gas::GasCostSummary, messages_checkpoint::CheckpointCommitment,
-  move_package::{TypeOrigin, UpgradeInfo}, object::{Data, Owner}, transaction::Argument, TypeTag,
+  move_package::{MovePackage, TypeOrigin, UpgradeInfo}, object::{Data, Owner}, transaction::Argument, TypeTag,
 };
 use crate::pb::sui::checkpoint::{self as pb};
 
@@ -62,12 +62,15 @@ pub fn convert_sui_json_value(source: &Value) -> pb::Value {
   let json_value = match source {
     Value::Null => pb::value::Value::Null(()),
     Value::Bool(val) => pb::value::Value::Bool(*val),
+    // With arbitrary_precision this is the number exactly as it was written, however wide it is
     Value::Number(val) => pb::value::Value::Number(val.to_string()),
     Value::String(val) => pb::value::Value::String(val.clone()),
     Value::Array(val) => pb::value::Value::Array(pb::ListOfValues {
       list: val.iter().map(convert_sui_json_value).collect(),
     }),
-    Value::Object(_) => pb::value::Value::Null(()),
+    Value::Object(val) => pb::value::Value::Object(pb::ValueMap {
+      map: val.iter().map(|(k, v)| (k.clone(), convert_sui_json_value(v))).collect(),
+    }),
   };
 
   pb::Value {
@@ -122,14 +125,9 @@ pub fn convert_data(data: &Data) -> pb::Data {
       has_public_transfer: m.has_public_transfer(),
       version: m.version().value(),
       contents: m.contents().to_vec(),
+      decoded: None,
     }),
-    Data::Package(p) => pb::data::Data::Package(pb::MovePackage {
-      id: Some(convert_sui_object(&p.id())),
-      version: p.version().value(),
-      type_origin_table: p.type_origin_table().iter().map(convert_type_origin).collect::<Vec<_>>(),
-      module_map: convert_module_map(&p.serialized_module_map()),
-      linkage_table: convert_linkage_table(p.linkage_table()),
-    }),
+    Data::Package(p) => pb::data::Data::Package(convert_move_package(p)),
   };
   
   pb::Data {
@@ -137,6 +135,16 @@ pub fn convert_data(data: &Data) -> pb::Data {
   }
 }
 
+pub fn convert_move_package(source: &MovePackage) -> pb::MovePackage {
+  pb::MovePackage {
+    id: Some(convert_sui_object(&source.id())),
+    version: source.version().value(),
+    type_origin_table: source.type_origin_table().iter().map(convert_type_origin).collect::<Vec<_>>(),
+    module_map: convert_module_map(&source.serialized_module_map()),
+    linkage_table: convert_linkage_table(source.linkage_table()),
+  }
+}
+
 fn convert_linkage_table(linkage_table: &BTreeMap<ObjectID, UpgradeInfo>) -> Vec<pb::LinkageTablePair> {
   let mut result = Vec::new();
 
@@ -236,3 +244,89 @@ pub fn convert_module_id(source: &ModuleId) -> pb::ModuleId {
     name: source.name().to_string(),
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use std::str::FromStr;
+  use serde_json::{json, Map, Number, Value};
+  use crate::pb::sui::checkpoint::{self as pb};
+  use super::convert_sui_json_value;
+
+  fn to_json(source: &pb::Value) -> Value {
+    match source.value.as_ref().unwrap() {
+      pb::value::Value::Null(()) => Value::Null,
+      pb::value::Value::Bool(val) => Value::Bool(*val),
+      pb::value::Value::Number(val) => Value::Number(Number::from_str(val).unwrap()),
+      pb::value::Value::String(val) => Value::String(val.clone()),
+      pb::value::Value::Array(val) => Value::Array(val.list.iter().map(to_json).collect()),
+      pb::value::Value::Object(val) => Value::Object(
+        val.map.iter().map(|(k, v)| (k.clone(), to_json(v))).collect::<Map<_, _>>()
+      ),
+    }
+  }
+
+  #[test]
+  fn converts_json_values_losslessly() {
+    let fixtures = [
+      "null",
+      "true",
+      "0",
+      "-42",
+      "18446744073709551615",
+      "-9223372036854775808",
+      "340282366920938463463374607431768211455",
+      "-170141183460469231731687303715884105728",
+      "0.1",
+      "1.7976931348623157e308",
+      "\"\"",
+      "\"0x2::sui::SUI\"",
+      "[]",
+      "[1, \"two\", [3], {\"four\": 4}]",
+      "{}",
+      r#"{"name": {"id": "0x6", "kind": {"tag": 1, "nested": [null, false]}}, "balance": "1000000000000000000000"}"#,
+      r#"{"u256": "115792089237316195423570985008687907853269984665640564039457584007913129639935"}"#,
+      r#"{"u256": 115792089237316195423570985008687907853269984665640564039457584007913129639935}"#,
+    ];
+
+    for fixture in fixtures {
+      let source = Value::from_str(fixture).unwrap();
+      assert_eq!(to_json(&convert_sui_json_value(&source)), source, "{}", fixture);
+    }
+  }
+
+  #[test]
+  fn keeps_numbers_as_exact_decimal_strings() {
+    let source = Value::from_str(
+      "[18446744073709551615, -9223372036854775808, 340282366920938463463374607431768211455, 0.30000000000000004]",
+    ).unwrap();
+    let pb_value = convert_sui_json_value(&source);
+
+    let Some(pb::value::Value::Array(list)) = pb_value.value else {
+      panic!("expected an array");
+    };
+    let numbers = list.list.into_iter().map(|value| value.value).collect::<Vec<_>>();
+
+    assert_eq!(numbers, vec![
+      Some(pb::value::Value::Number("18446744073709551615".to_string())),
+      Some(pb::value::Value::Number("-9223372036854775808".to_string())),
+      Some(pb::value::Value::Number("340282366920938463463374607431768211455".to_string())),
+      Some(pb::value::Value::Number("0.30000000000000004".to_string())),
+    ]);
+  }
+
+  #[test]
+  fn converts_objects_with_sorted_keys() {
+    let source = json!({"b": 1, "a": {"d": null, "c": [true]}});
+    let pb_value = convert_sui_json_value(&source);
+
+    let Some(pb::value::Value::Object(object)) = pb_value.value else {
+      panic!("expected an object");
+    };
+
+    assert_eq!(object.map.keys().collect::<Vec<_>>(), vec!["a", "b"]);
+    let Some(pb::value::Value::Object(nested)) = &object.map["a"].value else {
+      panic!("expected a nested object");
+    };
+    assert_eq!(nested.map.keys().collect::<Vec<_>>(), vec!["c", "d"]);
+  }
+}
@@ -0,0 +1,121 @@
+use sui_indexer::{handlers::EpochToCommit, types::IndexedEpochInfo};
+use crate::pb::sui::checkpoint::{self as pb};
+use super::common::convert_checkpoint_commitment;
+
+pub fn convert_epoch(source: &EpochToCommit) -> pb::Epoch {
+  pb::Epoch {
+    last_epoch: source.last_epoch.as_ref().map(convert_epoch_info),
+    new_epoch: Some(convert_epoch_info(&source.new_epoch)),
+    network_total_transactions: source.network_total_transactions,
+  }
+}
+
+fn convert_epoch_info(source: &IndexedEpochInfo) -> pb::EpochInfo {
+  pb::EpochInfo {
+    epoch: source.epoch,
+    first_checkpoint_id: source.first_checkpoint_id,
+    epoch_start_timestamp: source.epoch_start_timestamp,
+    reference_gas_price: source.reference_gas_price,
+    protocol_version: source.protocol_version,
+    total_stake: source.total_stake,
+    storage_fund_balance: source.storage_fund_balance,
+    system_state: source.system_state.clone(),
+    last_checkpoint_id: source.last_checkpoint_id,
+    epoch_end_timestamp: source.epoch_end_timestamp,
+    storage_fund_reinvestment: source.storage_fund_reinvestment,
+    storage_charge: source.storage_charge,
+    storage_rebate: source.storage_rebate,
+    stake_subsidy_amount: source.stake_subsidy_amount,
+    total_gas_fees: source.total_gas_fees,
+    total_stake_rewards_distributed: source.total_stake_rewards_distributed,
+    leftover_storage_fund_inflow: source.leftover_storage_fund_inflow,
+    epoch_commitments: source.epoch_commitments.iter().flatten().map(convert_checkpoint_commitment).collect(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use sui_indexer::{handlers::EpochToCommit, types::IndexedEpochInfo};
+  use crate::pb::sui::checkpoint::{self as pb};
+  use super::convert_epoch;
+
+  fn epoch_info(epoch: u64) -> IndexedEpochInfo {
+    IndexedEpochInfo {
+      epoch,
+      first_checkpoint_id: 1448000,
+      epoch_start_timestamp: 1682990756147,
+      reference_gas_price: 1000,
+      protocol_version: 8,
+      total_stake: 7_500_000_000,
+      storage_fund_balance: 1_000_000,
+      system_state: vec![1, 2, 3],
+      epoch_total_transactions: None,
+      last_checkpoint_id: None,
+      epoch_end_timestamp: None,
+      storage_fund_reinvestment: None,
+      storage_charge: None,
+      storage_rebate: None,
+      stake_subsidy_amount: None,
+      total_gas_fees: None,
+      total_stake_rewards_distributed: None,
+      leftover_storage_fund_inflow: None,
+      epoch_commitments: None,
+    }
+  }
+
+  #[test]
+  fn converts_epoch_boundary() {
+    let last_epoch = IndexedEpochInfo {
+      last_checkpoint_id: Some(1449999),
+      epoch_end_timestamp: Some(1683077156147),
+      total_gas_fees: Some(42),
+      epoch_commitments: Some(vec![]),
+      ..epoch_info(19)
+    };
+    let source = EpochToCommit {
+      last_epoch: Some(last_epoch),
+      new_epoch: IndexedEpochInfo {
+        first_checkpoint_id: 1450000,
+        reference_gas_price: 750,
+        protocol_version: 9,
+        ..epoch_info(20)
+      },
+      network_total_transactions: 1449228,
+    };
+
+    let pb_epoch = convert_epoch(&source);
+    let last_epoch = pb_epoch.last_epoch.unwrap();
+    let new_epoch = pb_epoch.new_epoch.unwrap();
+
+    assert_eq!(pb_epoch.network_total_transactions, 1449228);
+    assert_eq!(last_epoch.epoch, 19);
+    assert_eq!(last_epoch.last_checkpoint_id, Some(1449999));
+    assert_eq!(last_epoch.epoch_end_timestamp, Some(1683077156147));
+    assert_eq!(last_epoch.total_gas_fees, Some(42));
+    assert_eq!(new_epoch, pb::EpochInfo {
+      epoch: 20,
+      first_checkpoint_id: 1450000,
+      epoch_start_timestamp: 1682990756147,
+      reference_gas_price: 750,
+      protocol_version: 9,
+      total_stake: 7_500_000_000,
+      storage_fund_balance: 1_000_000,
+      system_state: vec![1, 2, 3],
+      ..Default::default()
+    });
+  }
+
+  #[test]
+  fn converts_genesis_epoch() {
+    let source = EpochToCommit {
+      last_epoch: None,
+      new_epoch: epoch_info(0),
+      network_total_transactions: 0,
+    };
+
+    let pb_epoch = convert_epoch(&source);
+
+    assert!(pb_epoch.last_epoch.is_none());
+    assert_eq!(pb_epoch.new_epoch.unwrap().epoch, 0);
+  }
+}
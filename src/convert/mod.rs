@@ -7,3 +7,8 @@ pub mod sui_event;
 pub mod execution_status;
 pub mod display_update;
 pub mod tx_object_change;
+pub mod checkpoint_data;
+pub mod tx_index;
+pub mod package;
+pub mod epoch;
+pub mod move_value;
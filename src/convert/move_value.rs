@@ -0,0 +1,116 @@
+use eyre::Result;
+use move_core_types::annotated_value::{MoveStruct, MoveValue};
+use crate::pb::sui::checkpoint::{self as pb};
+
+pub fn convert_move_value(source: &MoveValue) -> pb::Value {
+  let value = match source {
+    MoveValue::Bool(val) => pb::value::Value::Bool(*val),
+    MoveValue::U8(val) => pb::value::Value::Number(val.to_string()),
+    MoveValue::U16(val) => pb::value::Value::Number(val.to_string()),
+    MoveValue::U32(val) => pb::value::Value::Number(val.to_string()),
+    MoveValue::U64(val) => pb::value::Value::String(val.to_string()),
+    MoveValue::U128(val) => pb::value::Value::String(val.to_string()),
+    MoveValue::U256(val) => pb::value::Value::String(val.to_string()),
+    MoveValue::Address(val) | MoveValue::Signer(val) => pb::value::Value::String(val.to_canonical_string(false)),
+    MoveValue::Vector(val) => pb::value::Value::Array(pb::ListOfValues {
+      list: val.iter().map(convert_move_value).collect(),
+    }),
+    MoveValue::Struct(val) => pb::value::Value::Object(convert_move_struct(val)),
+  };
+
+  pb::Value {
+    value: Some(value),
+  }
+}
+
+fn convert_move_struct(source: &MoveStruct) -> pb::ValueMap {
+  pb::ValueMap {
+    map: source.fields.iter().map(|(name, value)| (name.to_string(), convert_move_value(value))).collect(),
+  }
+}
+
+/// A value that could not be decoded is flagged with the reason instead of failing the whole checkpoint
+pub fn convert_decoded_value(source: Result<MoveValue>) -> pb::DecodedValue {
+  let decoded = match source {
+    Ok(value) => pb::decoded_value::Decoded::Value(convert_move_value(&value)),
+    Err(err) => pb::decoded_value::Decoded::Error(err.to_string()),
+  };
+
+  pb::DecodedValue {
+    decoded: Some(decoded),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{collections::BTreeMap, str::FromStr};
+  use eyre::eyre;
+  use move_core_types::{
+    account_address::AccountAddress, annotated_value::{MoveStruct, MoveValue}, identifier::Identifier,
+    language_storage::StructTag, u256::U256,
+  };
+  use crate::pb::sui::checkpoint::{self as pb};
+  use super::{convert_decoded_value, convert_move_value};
+
+  fn string(val: &str) -> pb::Value {
+    pb::Value {value: Some(pb::value::Value::String(val.to_string()))}
+  }
+
+  fn number(val: &str) -> pb::Value {
+    pb::Value {value: Some(pb::value::Value::Number(val.to_string()))}
+  }
+
+  #[test]
+  fn converts_nested_structs() {
+    let source = MoveValue::Struct(MoveStruct {
+      type_: StructTag::from_str("0x2::pool::SwapEvent").unwrap(),
+      fields: vec![
+        (Identifier::new("sender").unwrap(), MoveValue::Address(AccountAddress::from_str("0x6").unwrap())),
+        (Identifier::new("fee_bps").unwrap(), MoveValue::U16(30)),
+        (Identifier::new("amount_in").unwrap(), MoveValue::U64(u64::MAX)),
+        (Identifier::new("liquidity").unwrap(), MoveValue::U128(u128::MAX)),
+        (Identifier::new("sqrt_price").unwrap(), MoveValue::U256(U256::max_value())),
+        (Identifier::new("path").unwrap(), MoveValue::Vector(vec![MoveValue::U8(1), MoveValue::U8(2)])),
+        (Identifier::new("info").unwrap(), MoveValue::Struct(MoveStruct {
+          type_: StructTag::from_str("0x2::pool::Info").unwrap(),
+          fields: vec![(Identifier::new("active").unwrap(), MoveValue::Bool(true))],
+        })),
+      ],
+    });
+
+    let mut info = BTreeMap::new();
+    info.insert("active".to_string(), pb::Value {value: Some(pb::value::Value::Bool(true))});
+
+    let mut expected = BTreeMap::new();
+    expected.insert(
+      "sender".to_string(),
+      string("0000000000000000000000000000000000000000000000000000000000000006"),
+    );
+    expected.insert("fee_bps".to_string(), number("30"));
+    expected.insert("amount_in".to_string(), string("18446744073709551615"));
+    expected.insert("liquidity".to_string(), string("340282366920938463463374607431768211455"));
+    expected.insert(
+      "sqrt_price".to_string(),
+      string("115792089237316195423570985008687907853269984665640564039457584007913129639935"),
+    );
+    expected.insert("path".to_string(), pb::Value {
+      value: Some(pb::value::Value::Array(pb::ListOfValues {list: vec![number("1"), number("2")]})),
+    });
+    expected.insert("info".to_string(), pb::Value {
+      value: Some(pb::value::Value::Object(pb::ValueMap {map: info})),
+    });
+
+    assert_eq!(convert_move_value(&source), pb::Value {
+      value: Some(pb::value::Value::Object(pb::ValueMap {map: expected})),
+    });
+  }
+
+  #[test]
+  fn flags_values_that_could_not_be_decoded() {
+    let decoded = convert_decoded_value(Err(eyre!("could not resolve the layout of 0xabc::pool::SwapEvent")));
+
+    assert_eq!(decoded.decoded, Some(pb::decoded_value::Decoded::Error(
+      "could not resolve the layout of 0xabc::pool::SwapEvent".to_string(),
+    )));
+  }
+}
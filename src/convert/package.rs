@@ -0,0 +1,95 @@
+use std::collections::HashSet;
+use sui_indexer::types::IndexedPackage;
+use crate::pb::sui::checkpoint::{self as pb};
+use super::common::{convert_move_package, convert_sui_object};
+
+pub fn convert_indexed_package(source: &IndexedPackage) -> pb::IndexedPackage {
+  pb::IndexedPackage {
+    package_id: Some(convert_sui_object(&source.package_id)),
+    move_package: Some(convert_move_package(&source.move_package)),
+    checkpoint_sequence_number: source.checkpoint_sequence_number,
+  }
+}
+
+/// Converts the packages of a checkpoint keeping only the first occurrence of each package id
+pub fn convert_indexed_packages(source: &[IndexedPackage]) -> Vec<pb::IndexedPackage> {
+  let mut seen = HashSet::new();
+
+  source.iter()
+  .filter(|package| seen.insert(package.package_id))
+  .map(convert_indexed_package)
+  .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{collections::{BTreeMap, HashMap}, str::FromStr};
+  use sui_indexer::types::IndexedPackage;
+  use sui_types::{base_types::{ObjectID, SequenceNumber}, move_package::MovePackage};
+  use crate::pb::sui::checkpoint::{self as pb};
+  use super::{convert_indexed_package, convert_indexed_packages};
+
+  fn indexed_package(package_id: &str) -> IndexedPackage {
+    let package_id = ObjectID::from_str(package_id).unwrap();
+    let mut module_map = BTreeMap::new();
+    module_map.insert("coin".to_string(), vec![1, 2, 3]);
+
+    IndexedPackage {
+      package_id,
+      move_package: MovePackage::new(
+        package_id,
+        SequenceNumber::from_u64(1),
+        module_map,
+        u64::MAX,
+        vec![],
+        BTreeMap::new(),
+      ).unwrap(),
+      checkpoint_sequence_number: 1448000,
+    }
+  }
+
+  #[test]
+  fn converts_indexed_package() {
+    let source = indexed_package("0x0000000000000000000000000000000000000000000000000000000000000002");
+    let pb_package = convert_indexed_package(&source);
+
+    let package_id = pb::ObjectId {
+      account_address: "0000000000000000000000000000000000000000000000000000000000000002".to_string(),
+    };
+    let mut module_map = HashMap::new();
+    module_map.insert("coin".to_string(), vec![1, 2, 3]);
+
+    let expected = pb::IndexedPackage {
+      package_id: Some(package_id.clone()),
+      move_package: Some(pb::MovePackage {
+        id: Some(package_id),
+        version: 1,
+        type_origin_table: vec![],
+        module_map,
+        linkage_table: vec![],
+      }),
+      checkpoint_sequence_number: 1448000,
+    };
+
+    assert_eq!(pb_package, expected);
+  }
+
+  #[test]
+  fn converts_each_package_once() {
+    let source = vec![
+      indexed_package("0x0000000000000000000000000000000000000000000000000000000000000002"),
+      indexed_package("0x0000000000000000000000000000000000000000000000000000000000000003"),
+      indexed_package("0x0000000000000000000000000000000000000000000000000000000000000002"),
+    ];
+
+    let pb_packages = convert_indexed_packages(&source);
+    let package_ids = pb_packages.iter()
+    .map(|package| package.package_id.clone().unwrap().account_address)
+    .collect::<Vec<_>>();
+
+    assert_eq!(package_ids, vec![
+      "0000000000000000000000000000000000000000000000000000000000000002".to_string(),
+      "0000000000000000000000000000000000000000000000000000000000000003".to_string(),
+    ]);
+  }
+}
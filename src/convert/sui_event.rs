@@ -1,7 +1,8 @@
+use eyre::eyre;
 use sui_indexer::types::IndexedEvent;
-use sui_types::event::Event;
-use crate::pb::sui::checkpoint::{self as pb};
-use super::common::{convert_struct_tag, convert_sui_address, convert_sui_object};
+use sui_types::{event::Event, parse_sui_struct_tag};
+use crate::{pb::sui::checkpoint::{self as pb}, sui::move_decoder::MoveDecoder};
+use super::{common::{convert_struct_tag, convert_sui_address, convert_sui_object}, move_value::convert_decoded_value};
 
 pub fn convert_event(source: &Event) -> pb::Event {
   pb::Event {
@@ -10,9 +11,14 @@ pub fn convert_event(source: &Event) -> pb::Event {
     sender: convert_sui_address(&source.sender),
     r#type: Some(convert_struct_tag(&source.type_)),
     contents: source.contents.clone(),
+    decoded: None,
   }
 }
 
+pub fn decode_event(decoder: &MoveDecoder, source: &Event) -> pb::DecodedValue {
+  convert_decoded_value(decoder.decode_struct(&source.type_, &source.contents))
+}
+
 pub fn convert_indexed_event(source: &IndexedEvent) -> pb::IndexedEvent {
   pb::IndexedEvent {
     tx_sequence_number: source.tx_sequence_number,
@@ -25,20 +31,39 @@ pub fn convert_indexed_event(source: &IndexedEvent) -> pb::IndexedEvent {
     event_type: source.event_type.clone(),
     bcs: source.bcs.clone(),
     timestamp_ms: source.timestamp_ms,
+    decoded: None,
   }
 }
 
+pub fn decode_indexed_event(decoder: &MoveDecoder, source: &IndexedEvent) -> pb::DecodedValue {
+  let decoded = parse_sui_struct_tag(&source.event_type)
+  .map_err(|err| eyre!("invalid event type {}: {}", source.event_type, err))
+  .and_then(|struct_tag| decoder.decode_struct(&struct_tag, &source.bcs));
+
+  convert_decoded_value(decoded)
+}
+
 #[cfg(test)]
 mod tests {
   use std::str::FromStr;
 
 use sui_indexer::types::IndexedEvent;
   use sui_types::{base_types::{ObjectID, SuiAddress}, digests::TransactionDigest};
-  use crate::{convert::sui_event::convert_indexed_event, pb::sui::checkpoint::{self as pb, ObjectId}};
+  use crate::{
+    convert::sui_event::{convert_indexed_event, decode_indexed_event}, pb::sui::checkpoint::{self as pb, ObjectId},
+    test_utils::system_move_decoder,
+  };
 
-  #[test]
-  fn converts_indexed_event() {
-    let source = IndexedEvent {
+  fn indexed_event(event_type: &str, bcs: Vec<u8>) -> IndexedEvent {
+    IndexedEvent {
+      event_type: event_type.to_string(),
+      bcs,
+      ..sample_indexed_event()
+    }
+  }
+
+  fn sample_indexed_event() -> IndexedEvent {
+    IndexedEvent {
         tx_sequence_number: 1,
         event_sequence_number: 10,
         checkpoint_sequence_number: 1000,
@@ -49,7 +74,12 @@ use sui_indexer::types::IndexedEvent;
         event_type: "event_1".to_string(),
         bcs: vec![1,2 ,3 , 4, 5],
         timestamp_ms: 1682990756147,
-    };
+    }
+  }
+
+  #[test]
+  fn converts_indexed_event() {
+    let source = sample_indexed_event();
     let pb_indexed_event = convert_indexed_event(&source);
     let expected = pb::IndexedEvent {
       tx_sequence_number: 1,
@@ -64,8 +94,26 @@ use sui_indexer::types::IndexedEvent;
       event_type: "event_1".to_string(),
       bcs: vec![1,2 ,3 , 4, 5],
       timestamp_ms: 1682990756147,
+      decoded: None,
     };
 
     assert_eq!(expected, pb_indexed_event);
   }
+
+  #[test]
+  fn decodes_indexed_event_or_flags_it() {
+    let decoder = system_move_decoder();
+
+    let decoded = decode_indexed_event(&decoder, &indexed_event("0x2::object::ID", vec![7; 32]));
+    let Some(pb::decoded_value::Decoded::Value(value)) = decoded.decoded else {
+      panic!("expected a decoded struct");
+    };
+    let Some(pb::value::Value::Object(object)) = value.value else {
+      panic!("expected a struct");
+    };
+    assert_eq!(object.map["bytes"].value, Some(pb::value::Value::String("07".repeat(32))));
+
+    let unresolved = decode_indexed_event(&decoder, &indexed_event("0xabc::pool::SwapEvent", vec![1, 2, 3]));
+    assert!(matches!(unresolved.decoded, Some(pb::decoded_value::Decoded::Error(_))));
+  }
 }
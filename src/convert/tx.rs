@@ -1,21 +1,25 @@
+use std::collections::HashMap;
+use log::warn;
 use shared_crypto::intent::{AppId, Intent, IntentMessage, IntentScope, IntentVersion};
 use sui_indexer::types::IndexedTransaction;
 use sui_types::{
-  authenticator_state::ActiveJwk, base_types::ObjectID,
+  authenticator_state::ActiveJwk, base_types::ObjectID, crypto::{PublicKey, Signature, SignatureScheme, SuiSignature},
   messages_consensus::{ConsensusCommitPrologue, ConsensusCommitPrologueV2},
+  multisig::{MultiSig, MultiSigPublicKey}, signature::GenericSignature, zk_login_authenticator::ZkLoginAuthenticator,
   transaction::{
     Argument, AuthenticatorStateUpdate, CallArg, ChangeEpoch, Command, EndOfEpochTransactionKind, GasData, GenesisObject,
     GenesisTransaction, ObjectArg, ProgrammableMoveCall, ProgrammableTransaction, RandomnessStateUpdate, SenderSignedData,
     TransactionData, TransactionDataAPI, TransactionExpiration, TransactionKind,
   }, TypeTag
 };
-use crate::pb::sui::checkpoint::{self as pb};
+use crate::{pb::sui::checkpoint::{self as pb}, sui::move_decoder::MoveDecoder};
 
 use super::{
   common::{
     convert_data, convert_object_ref, convert_owner, convert_sui_address, convert_sui_argument, convert_sui_object,
     convert_type_tag,
-  }, sui_effects::convert_sui_effects, sui_event::convert_event, sui_object::convert_tx_object_change
+  }, move_value::convert_decoded_value, sui_effects::convert_sui_effects, sui_event::{convert_event, decode_event},
+  sui_object::convert_tx_object_change
 };
 
 fn convert_intent_message(source: &IntentMessage<TransactionData>) -> pb::IntentMessage {
@@ -200,7 +204,9 @@ fn convert_programmable_tx_kind(source: &ProgrammableTransaction) -> pb::transac
     };
 
     pb::CallArg {
-      call_arg: Some(call_arg)
+      call_arg: Some(call_arg),
+      pure_type: None,
+      pure_decoded: None,
     }
   }).collect::<Vec<_>>();
 
@@ -350,6 +356,100 @@ fn convert_sender_signed_data(source: &SenderSignedData) -> pb::SenderSignedTran
 
   pb::SenderSignedTransaction {
     intent_message: Some(convert_intent_message(&sender_signed_tx.intent_message)),
+    tx_signatures: sender_signed_tx.tx_signatures.iter().map(convert_generic_signature).collect(),
+  }
+}
+
+fn convert_generic_signature(source: &GenericSignature) -> pb::GenericSignature {
+  let signature = match source {
+    GenericSignature::Signature(s) => pb::generic_signature::Signature::SimpleSignature(convert_simple_signature(s)),
+    GenericSignature::MultiSig(ms) => pb::generic_signature::Signature::Multisig(convert_multisig(ms)),
+    GenericSignature::MultiSigLegacy(_) => pb::generic_signature::Signature::MultisigLegacy(()),
+    GenericSignature::ZkLoginAuthenticator(zk) => pb::generic_signature::Signature::ZkLoginAuthenticator(convert_zk_login_authenticator(zk)),
+  };
+
+  pb::GenericSignature {
+    signature: Some(signature),
+    bytes: source.as_ref().to_vec(),
+  }
+}
+
+fn convert_signature_scheme(source: SignatureScheme) -> pb::SignatureScheme {
+  let signature_scheme = match source {
+    SignatureScheme::ED25519 => pb::signature_scheme::SignatureScheme::Ed25519(()),
+    SignatureScheme::Secp256k1 => pb::signature_scheme::SignatureScheme::Secp256k1(()),
+    SignatureScheme::Secp256r1 => pb::signature_scheme::SignatureScheme::Secp256r1(()),
+    SignatureScheme::BLS12381 => pb::signature_scheme::SignatureScheme::Bls12381(()),
+    SignatureScheme::MultiSig => pb::signature_scheme::SignatureScheme::Multisig(()),
+    SignatureScheme::ZkLoginAuthenticator => pb::signature_scheme::SignatureScheme::ZkLoginAuthenticator(()),
+  };
+
+  pb::SignatureScheme {
+    signature_scheme: Some(signature_scheme),
+  }
+}
+
+fn convert_simple_signature(source: &Signature) -> pb::SimpleSignature {
+  pb::SimpleSignature {
+    scheme: Some(convert_signature_scheme(source.scheme())),
+    signature: source.signature_bytes().to_vec(),
+    public_key: source.public_key_bytes().to_vec(),
+  }
+}
+
+fn convert_multisig(source: &MultiSig) -> pb::MultiSig {
+  // The bitmap has already been validated when the transaction was executed so a malformed one means the data is
+  // corrupted. Its signatures are left out rather than attributed to the wrong members.
+  let indices = source.get_indices().unwrap_or_else(|err| {
+    warn!("Skipping the signatures of a multisig with a malformed bitmap: {}", err);
+    vec![]
+  });
+  let pubkeys = source.get_pk().pubkeys();
+  let mut bitmap = 0u32;
+  let mut sigs = vec![];
+
+  for (sig, index) in source.get_sigs().iter().zip(indices) {
+    if u32::from(index) >= u32::BITS {
+      warn!("Skipping the signature of multisig member {} which does not fit in the bitmap", index);
+      continue
+    }
+
+    bitmap |= 1 << index;
+    sigs.push(pb::CompressedSignature {
+      // a compressed signature has the scheme of the member that produced it
+      scheme: pubkeys.get(index as usize).map(|(pk, _)| convert_signature_scheme(pk.scheme())),
+      signature: sig.as_ref().to_vec(),
+    });
+  }
+
+  pb::MultiSig {
+    sigs,
+    bitmap,
+    multisig_pk: Some(convert_multisig_pk(source.get_pk())),
+  }
+}
+
+fn convert_multisig_pk(source: &MultiSigPublicKey) -> pb::MultiSigPublicKey {
+  pb::MultiSigPublicKey {
+    members: source.pubkeys().iter().map(|(pk, weight)| convert_multisig_member(pk, *weight)).collect(),
+    threshold: *source.threshold() as u32,
+  }
+}
+
+fn convert_multisig_member(pk: &PublicKey, weight: u8) -> pb::MultiSigMember {
+  pb::MultiSigMember {
+    scheme: Some(convert_signature_scheme(pk.scheme())),
+    public_key: pk.as_ref().to_vec(),
+    weight: weight as u32,
+  }
+}
+
+fn convert_zk_login_authenticator(source: &ZkLoginAuthenticator) -> pb::ZkLoginAuthenticator {
+  pb::ZkLoginAuthenticator {
+    iss: source.get_iss().to_string(),
+    address_seed: source.inputs.get_address_seed().to_string(),
+    max_epoch: source.get_max_epoch(),
+    user_signature: Some(convert_simple_signature(&source.user_signature)),
   }
 }
 
@@ -389,21 +489,112 @@ pub fn convert_transaction(source: &IndexedTransaction) -> pb::Transaction {
   }
 }
 
+/// Attaches the decoded contents to the events of an already converted transaction
+pub fn decode_transaction_events(decoder: &MoveDecoder, source: &IndexedTransaction, target: &mut pb::Transaction) {
+  for (event, pb_event) in source.events.iter().zip(target.events.iter_mut()) {
+    pb_event.decoded = Some(decode_event(decoder, event));
+  }
+}
+
+/// Attaches the inferred type and the decoded value to the pure inputs of an already converted programmable
+/// transaction
+pub fn decode_pure_inputs(decoder: &MoveDecoder, source: &IndexedTransaction, target: &mut pb::Transaction) {
+  let TransactionKind::ProgrammableTransaction(pt) = source.sender_signed_data.transaction_data().kind() else {
+    return
+  };
+
+  if let Some(pb_pt) = programmable_tx_mut(target) {
+    decode_programmable_tx_inputs(decoder, pt, pb_pt);
+  }
+}
+
+fn programmable_tx_mut(target: &mut pb::Transaction) -> Option<&mut pb::ProgrammableTransaction> {
+  let tx_data = target.sender_signed_data.first_mut()?.intent_message.as_mut()?.value.as_mut()?;
+  let Some(pb::transaction_data::TxData::V1(tx_data_v1)) = tx_data.tx_data.as_mut() else {
+    return None
+  };
+
+  match tx_data_v1.kind.as_mut()?.transaction_kind.as_mut()? {
+    pb::transaction_kind::TransactionKind::ProgrammableTx(pt) => Some(pt),
+    _ => None,
+  }
+}
+
+fn decode_programmable_tx_inputs(
+  decoder: &MoveDecoder,
+  source: &ProgrammableTransaction,
+  target: &mut pb::ProgrammableTransaction,
+) {
+  let pure_types = infer_pure_input_types(decoder, source);
+
+  for (index, (input, pb_input)) in source.inputs.iter().zip(target.inputs.iter_mut()).enumerate() {
+    let (CallArg::Pure(bytes), Some(type_tag)) = (input, pure_types.get(&(index as u16))) else {
+      continue
+    };
+
+    pb_input.pure_type = Some(convert_type_tag(type_tag));
+    pb_input.pure_decoded = Some(convert_decoded_value(decoder.decode(type_tag, bytes)));
+  }
+}
+
+/// Pure values are untyped BCS bytes so their type is taken from the first command that uses them. Inputs that
+/// are only used by move calls into packages that haven't been seen yet are left out.
+fn infer_pure_input_types(decoder: &MoveDecoder, source: &ProgrammableTransaction) -> HashMap<u16, TypeTag> {
+  let mut pure_types = HashMap::new();
+  let mut infer = |argument: &Argument, type_tag: TypeTag| {
+    if let Argument::Input(index) = argument {
+      pure_types.entry(*index).or_insert(type_tag);
+    }
+  };
+
+  for command in &source.commands {
+    match command {
+      Command::MoveCall(mc) => {
+        let Ok(parameters) = decoder.function_parameters(&mc.package, &mc.module, &mc.function, &mc.type_arguments) else {
+          continue
+        };
+
+        for (argument, parameter) in mc.arguments.iter().zip(parameters) {
+          if let Some(type_tag) = parameter {
+            infer(argument, type_tag);
+          }
+        }
+      },
+      Command::SplitCoins(_, amounts) => amounts.iter().for_each(|amount| infer(amount, TypeTag::U64)),
+      Command::TransferObjects(_, recipient) => infer(recipient, TypeTag::Address),
+      Command::MakeMoveVec(Some(type_tag), elements) => {
+        elements.iter().for_each(|element| infer(element, type_tag.clone()))
+      },
+      _ => {},
+    }
+  }
+
+  pure_types
+}
+
 #[cfg(test)]
 mod tests {
   use std::str::FromStr;
   use move_core_types::{account_address::AccountAddress, language_storage::StructTag};
-  use shared_crypto::intent::{AppId, Intent, IntentScope};
+  use shared_crypto::intent::{AppId, Intent, IntentMessage, IntentScope};
   use sui_indexer::types::{TransactionKind as IndexerTxKind, IndexedObjectChange, IndexedTransaction};
   use sui_types::{
-    base_types::{ObjectID, SequenceNumber, SuiAddress}, crypto::{Ed25519SuiSignature, Signature},
+    base_types::{ObjectID, SequenceNumber, SuiAddress},
+    crypto::{get_key_pair, Ed25519KeyPair, Ed25519SuiSignature, Signature, SuiKeyPair},
     digests::{ObjectDigest, TransactionDigest}, effects::TransactionEffects, execution_status::ExecutionStatus,
-    gas::GasCostSummary, messages_consensus::ConsensusCommitPrologue, object::Owner, signature::GenericSignature,
-    transaction::{GasData, SenderSignedData, TransactionData, TransactionDataV1, TransactionExpiration, TransactionKind},
-    Identifier
+    gas::GasCostSummary, messages_consensus::ConsensusCommitPrologue, multisig::{MultiSig, MultiSigPublicKey},
+    object::Owner, signature::GenericSignature,
+    transaction::{
+      Argument, CallArg, Command, GasData, ProgrammableMoveCall, ProgrammableTransaction, SenderSignedData,
+      TransactionData, TransactionDataV1, TransactionExpiration, TransactionKind,
+    },
+    Identifier, TypeTag,
   };
-
-use crate::{convert::tx::convert_transaction, pb::sui::checkpoint::{self as pb, TransactionBlockEffectsV1}};
+  use crate::{
+    convert::tx::convert_transaction, pb::sui::checkpoint::{self as pb, TransactionBlockEffectsV1},
+    test_utils::system_move_decoder,
+  };
+  use super::{convert_generic_signature, convert_programmable_tx_kind, decode_programmable_tx_inputs};
 
   #[test]
   fn converts_transaction() {
@@ -547,7 +738,17 @@ use crate::{convert::tx::convert_transaction, pb::sui::checkpoint::{self as pb,
                   }),
                 })),
               }),
-            })
+            }),
+            tx_signatures: vec![pb::GenericSignature {
+              signature: Some(pb::generic_signature::Signature::SimpleSignature(pb::SimpleSignature {
+                scheme: Some(pb::SignatureScheme {
+                  signature_scheme: Some(pb::signature_scheme::SignatureScheme::Ed25519(())),
+                }),
+                signature: vec![0; 64],
+                public_key: vec![0; 32],
+              })),
+              bytes: vec![0; 97],
+            }],
           } 
         ],
         effects: Some(pb::TransactionBlockEffects {
@@ -633,4 +834,105 @@ use crate::{convert::tx::convert_transaction, pb::sui::checkpoint::{self as pb,
 
     assert_eq!(expected, pb_tx);
   }
+
+  #[test]
+  fn converts_multisig() {
+    let keys = (0..3)
+    .map(|_| SuiKeyPair::Ed25519(get_key_pair::<Ed25519KeyPair>().1))
+    .collect::<Vec<_>>();
+    let multisig_pk = MultiSigPublicKey::new(
+      keys.iter().map(|key| key.public()).collect(),
+      vec![1, 1, 2],
+      2,
+    ).unwrap();
+    let msg = IntentMessage::new(Intent::sui_transaction(), "multisig");
+    // members 0 and 2 sign
+    let sigs = vec![Signature::new_secure(&msg, &keys[0]), Signature::new_secure(&msg, &keys[2])];
+    let source = GenericSignature::MultiSig(MultiSig::combine(sigs, multisig_pk).unwrap());
+
+    let pb_signature = convert_generic_signature(&source);
+    let Some(pb::generic_signature::Signature::Multisig(multisig)) = pb_signature.signature else {
+      panic!("expected a multisig");
+    };
+    let multisig_pk = multisig.multisig_pk.unwrap();
+    let ed25519 = pb::SignatureScheme {
+      signature_scheme: Some(pb::signature_scheme::SignatureScheme::Ed25519(())),
+    };
+
+    assert_eq!(pb_signature.bytes, source.as_ref());
+    assert_eq!(multisig.bitmap, 0b101);
+    assert_eq!(multisig.sigs.len(), 2);
+    assert!(multisig.sigs.iter().all(|sig| sig.scheme == Some(ed25519.clone()) && sig.signature.len() == 64));
+    assert_eq!(multisig_pk.threshold, 2);
+    assert_eq!(multisig_pk.members.iter().map(|member| member.weight).collect::<Vec<_>>(), vec![1, 1, 2]);
+    assert_eq!(multisig_pk.members[2].public_key, keys[2].public().as_ref());
+  }
+
+  #[test]
+  fn skips_signatures_of_multisig_with_malformed_bitmap() {
+    let keys = (0..2)
+    .map(|_| SuiKeyPair::Ed25519(get_key_pair::<Ed25519KeyPair>().1))
+    .collect::<Vec<_>>();
+    let multisig_pk = MultiSigPublicKey::new(keys.iter().map(|key| key.public()).collect(), vec![1, 1], 1).unwrap();
+    let msg = IntentMessage::new(Intent::sui_transaction(), "multisig");
+    let valid = MultiSig::combine(vec![Signature::new_secure(&msg, &keys[0])], multisig_pk.clone()).unwrap();
+    // Only 10 members are allowed so bit 12 can never be set
+    let source = GenericSignature::MultiSig(MultiSig::insecure_new(valid.get_sigs().to_vec(), 1 << 12, multisig_pk));
+
+    let Some(pb::generic_signature::Signature::Multisig(multisig)) = convert_generic_signature(&source).signature else {
+      panic!("expected a multisig");
+    };
+
+    assert_eq!(multisig.bitmap, 0);
+    assert!(multisig.sigs.is_empty());
+    assert_eq!(multisig.multisig_pk.unwrap().members.len(), 2);
+  }
+
+  #[test]
+  fn decodes_pure_inputs_with_inferred_types() {
+    let decoder = system_move_decoder();
+    let recipient = SuiAddress::from_str("0x57ab74a5ea1a2ddfb1ebf5b4a7a7de2e3ca0d0046a43f2bbd2bd4cb3b08ea0b7").unwrap();
+
+    let source = ProgrammableTransaction {
+      inputs: vec![
+        CallArg::Pure(bcs::to_bytes(&1_000_000_000u64).unwrap()),
+        CallArg::Pure(bcs::to_bytes(&recipient).unwrap()),
+        CallArg::Pure(bcs::to_bytes(&250u64).unwrap()),
+        CallArg::Pure(bcs::to_bytes("unused").unwrap()),
+      ],
+      commands: vec![
+        Command::SplitCoins(Argument::GasCoin, vec![Argument::Input(0)]),
+        Command::MoveCall(Box::new(ProgrammableMoveCall {
+          package: ObjectID::from_str("0x2").unwrap(),
+          module: Identifier::new("pay").unwrap(),
+          function: Identifier::new("split").unwrap(),
+          type_arguments: vec![TypeTag::from_str("0x2::sui::SUI").unwrap()],
+          arguments: vec![Argument::NestedResult(0, 0), Argument::Input(2)],
+        })),
+        Command::TransferObjects(vec![Argument::NestedResult(0, 0)], Argument::Input(1)),
+      ],
+    };
+    let pb::transaction_kind::TransactionKind::ProgrammableTx(mut pb_pt) = convert_programmable_tx_kind(&source) else {
+      panic!("expected a programmable transaction");
+    };
+
+    decode_programmable_tx_inputs(&decoder, &source, &mut pb_pt);
+
+    let decoded = pb_pt.inputs.into_iter()
+    .map(|input| (input.pure_type.and_then(|t| t.type_tag), input.pure_decoded.and_then(|d| d.decoded)))
+    .collect::<Vec<_>>();
+    let string = |val: &str| Some(pb::decoded_value::Decoded::Value(pb::Value {
+      value: Some(pb::value::Value::String(val.to_string())),
+    }));
+
+    assert_eq!(decoded, vec![
+      (Some(pb::type_tag::TypeTag::U64(())), string("1000000000")),
+      (
+        Some(pb::type_tag::TypeTag::Address(())),
+        string("57ab74a5ea1a2ddfb1ebf5b4a7a7de2e3ca0d0046a43f2bbd2bd4cb3b08ea0b7"),
+      ),
+      (Some(pb::type_tag::TypeTag::U64(())), string("250")),
+      (None, None),
+    ]);
+  }
 }
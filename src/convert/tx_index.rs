@@ -0,0 +1,98 @@
+use sui_indexer::types::TxIndex;
+use sui_types::base_types::ObjectID;
+use crate::pb::sui::checkpoint::{self as pb};
+use super::common::{convert_sui_address, convert_sui_object};
+
+pub fn convert_tx_index(source: &TxIndex) -> pb::TxIndex {
+  pb::TxIndex {
+    tx_sequence_number: source.tx_sequence_number,
+    transaction_digest: source.transaction_digest.base58_encode(),
+    checkpoint_sequence_number: source.checkpoint_sequence_number,
+    input_objects: source.input_objects.iter().map(convert_sui_object).collect(),
+    changed_objects: source.changed_objects.iter().map(convert_sui_object).collect(),
+    payers: source.payers.iter().map(convert_sui_address).collect(),
+    senders: source.senders.iter().map(convert_sui_address).collect(),
+    recipients: source.recipients.iter().map(convert_sui_address).collect(),
+    move_calls: source.move_calls.iter().map(convert_move_call_overview).collect(),
+  }
+}
+
+fn convert_move_call_overview(source: &(ObjectID, String, String)) -> pb::MoveCallOverview {
+  let (package, module, function) = source;
+
+  pb::MoveCallOverview {
+    package: Some(convert_sui_object(package)),
+    module: module.clone(),
+    function: function.clone(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::str::FromStr;
+  use sui_indexer::types::TxIndex;
+  use sui_types::{base_types::{ObjectID, SuiAddress}, digests::TransactionDigest};
+  use crate::pb::sui::checkpoint::{self as pb};
+  use super::convert_tx_index;
+
+  #[test]
+  fn converts_tx_index() {
+    let sender = SuiAddress::from_str("0x57ab74a5ea1a2ddfb1ebf5b4a7a7de2e3ca0d0046a43f2bbd2bd4cb3b08ea0b7").unwrap();
+    let recipient = SuiAddress::from_str("0x1d9bbc8fb9f77d4ac4fc1e4a6a7ecaba9d4a7c2bbd2dbe6d6f3f57b1f7f33b2d").unwrap();
+    let source = TxIndex {
+      tx_sequence_number: 1449228,
+      transaction_digest: TransactionDigest::from_str("D7CBWgtjcgMyn1YhRZ2q7okrmiCUYW4QA5gPZT6CRa2n").unwrap(),
+      checkpoint_sequence_number: 1448000,
+      input_objects: vec![ObjectID::from_str("0x0000000000000000000000000000000000000000000000000000000000000006").unwrap()],
+      changed_objects: vec![ObjectID::from_str("0x0000000000000000000000000000000000000000000000000000000000000005").unwrap()],
+      payers: vec![sender],
+      senders: vec![sender],
+      recipients: vec![recipient],
+      move_calls: vec![(ObjectID::from_str("0x0000000000000000000000000000000000000000000000000000000000000002").unwrap(), "coin".to_string(), "split".to_string())],
+    };
+
+    let expected = pb::TxIndex {
+      tx_sequence_number: 1449228,
+      transaction_digest: "D7CBWgtjcgMyn1YhRZ2q7okrmiCUYW4QA5gPZT6CRa2n".to_string(),
+      checkpoint_sequence_number: 1448000,
+      input_objects: vec![pb::ObjectId {
+        account_address: "0000000000000000000000000000000000000000000000000000000000000006".to_string(),
+      }],
+      changed_objects: vec![pb::ObjectId {
+        account_address: "0000000000000000000000000000000000000000000000000000000000000005".to_string(),
+      }],
+      payers: vec!["57ab74a5ea1a2ddfb1ebf5b4a7a7de2e3ca0d0046a43f2bbd2bd4cb3b08ea0b7".to_string()],
+      senders: vec!["57ab74a5ea1a2ddfb1ebf5b4a7a7de2e3ca0d0046a43f2bbd2bd4cb3b08ea0b7".to_string()],
+      recipients: vec!["1d9bbc8fb9f77d4ac4fc1e4a6a7ecaba9d4a7c2bbd2dbe6d6f3f57b1f7f33b2d".to_string()],
+      move_calls: vec![pb::MoveCallOverview {
+        package: Some(pb::ObjectId {
+          account_address: "0000000000000000000000000000000000000000000000000000000000000002".to_string(),
+        }),
+        module: "coin".to_string(),
+        function: "split".to_string(),
+      }],
+    };
+
+    assert_eq!(convert_tx_index(&source), expected);
+  }
+
+  #[test]
+  fn converts_tx_index_without_move_calls() {
+    let source = TxIndex {
+      tx_sequence_number: 0,
+      transaction_digest: TransactionDigest::from_str("D7CBWgtjcgMyn1YhRZ2q7okrmiCUYW4QA5gPZT6CRa2n").unwrap(),
+      checkpoint_sequence_number: 0,
+      input_objects: vec![],
+      changed_objects: vec![],
+      payers: vec![],
+      senders: vec![],
+      recipients: vec![],
+      move_calls: vec![],
+    };
+
+    let pb_tx_index = convert_tx_index(&source);
+
+    assert_eq!(pb_tx_index.transaction_digest, "D7CBWgtjcgMyn1YhRZ2q7okrmiCUYW4QA5gPZT6CRa2n");
+    assert!(pb_tx_index.move_calls.is_empty());
+  }
+}
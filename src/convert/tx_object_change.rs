@@ -1,7 +1,17 @@
 
+use move_core_types::language_storage::StructTag;
 use sui_indexer::{handlers::TransactionObjectChangesToCommit, types::OwnerType};
-use crate::pb::sui::checkpoint::{self as pb, owner_type};
-use super::common::{convert_data, convert_owner, convert_sui_address, convert_sui_object};
+use sui_types::{dynamic_field::{DynamicFieldInfo, DynamicFieldType}, object::Data};
+use crate::{
+  pb::sui::checkpoint::{self as pb, dynamic_field_type, owner_type}, sui::move_decoder::MoveDecoder,
+  type_filter::TypeFilter,
+};
+use super::{
+  common::{
+    convert_data, convert_owner, convert_sui_address, convert_sui_json_value, convert_sui_object, convert_type_tag,
+  },
+  move_value::convert_decoded_value,
+};
 
 pub fn convert_tx_object_changes(source: &TransactionObjectChangesToCommit) -> pb::TransactionObjectChange {
   let changed_objects = source.changed_objects.iter().map(|changed_object| {
@@ -16,6 +26,7 @@ pub fn convert_tx_object_changes(source: &TransactionObjectChangesToCommit) -> p
       object: Some(convert_object(&changed_object.object)),
       coin_type: changed_object.coin_type.clone(),
       coin_balance: changed_object.coin_balance,
+      df_info: changed_object.df_info.as_ref().map(convert_df_info),
     }
   }).collect::<Vec<_>>();
 
@@ -34,6 +45,30 @@ pub fn convert_tx_object_changes(source: &TransactionObjectChangesToCommit) -> p
   }
 }
 
+/// Attaches the decoded contents to the Move objects of already converted object changes. Packages and types
+/// that don't pass the filter are left as raw bytes.
+pub fn decode_tx_object_changes(
+  decoder: &MoveDecoder,
+  filter: &TypeFilter,
+  source: &TransactionObjectChangesToCommit,
+  target: &mut pb::TransactionObjectChange,
+) {
+  for (changed_object, pb_changed_object) in source.changed_objects.iter().zip(target.changed_objects.iter_mut()) {
+    let Data::Move(move_object) = &changed_object.object.data else {
+      continue
+    };
+    let struct_tag = StructTag::from(move_object.type_().clone());
+    if !filter.is_allowed(&struct_tag) {
+      continue
+    }
+
+    let pb_data = pb_changed_object.object.as_mut().and_then(|object| object.data.as_mut());
+    if let Some(pb::Data {data: Some(pb::data::Data::Move(pb_move_object))}) = pb_data {
+      pb_move_object.decoded = Some(convert_decoded_value(decoder.decode_struct(&struct_tag, move_object.contents())));
+    }
+  }
+}
+
 fn convert_object(source: &sui_types::object::Object) -> pb::Object {
   pb::Object {
     data: Some(convert_data(&source.data)),
@@ -43,6 +78,32 @@ fn convert_object(source: &sui_types::object::Object) -> pb::Object {
   }
 }
 
+fn convert_df_info(source: &DynamicFieldInfo) -> pb::DynamicFieldInfo {
+  pb::DynamicFieldInfo {
+    name: Some(pb::DynamicFieldName {
+      r#type: Some(convert_type_tag(&source.name.type_)),
+      value: Some(convert_sui_json_value(&source.name.value)),
+    }),
+    bcs_name: source.bcs_name.clone(),
+    r#type: Some(convert_dynamic_field_type(&source.type_)),
+    object_type: source.object_type.clone(),
+    object_id: Some(convert_sui_object(&source.object_id)),
+    version: source.version.value(),
+    digest: source.digest.base58_encode(),
+  }
+}
+
+fn convert_dynamic_field_type(source: &DynamicFieldType) -> pb::DynamicFieldType {
+  let dynamic_field_type = match source {
+    DynamicFieldType::DynamicField => dynamic_field_type::DynamicFieldType::DynamicField(()),
+    DynamicFieldType::DynamicObject => dynamic_field_type::DynamicFieldType::DynamicObject(()),
+  };
+
+  pb::DynamicFieldType {
+    dynamic_field_type: Some(dynamic_field_type),
+  }
+}
+
 fn convert_owner_type(source: &OwnerType) -> pb::OwnerType {
   let owner_type = match source {
     OwnerType::Immutable => owner_type::OwnerType::Immutable(()),
@@ -60,13 +121,19 @@ mod tests {
   use std::str::FromStr;
   use move_core_types::{account_address::AccountAddress, language_storage::StructTag};
 use sui_indexer::{handlers::TransactionObjectChangesToCommit, types::{IndexedObject, OwnerType}};
-  use sui_types::{base_types::{ObjectID, SequenceNumber}, digests::{ObjectDigest, TransactionDigest}, object::{MoveObject, Object, Owner}, Identifier};
-  use crate::pb::sui::checkpoint::{self as pb};
-  use super::convert_tx_object_changes;
+  use serde_json::json;
+  use sui_types::{
+    base_types::{ObjectID, SequenceNumber, SuiAddress}, digests::{ObjectDigest, TransactionDigest},
+    dynamic_field::{DynamicFieldInfo, DynamicFieldName, DynamicFieldType}, object::{MoveObject, Object, Owner},
+    Identifier, TypeTag,
+  };
+  use crate::{
+    pb::sui::checkpoint::{self as pb}, test_utils::system_move_decoder, type_filter::TypeFilter,
+  };
+  use super::{convert_tx_object_changes, decode_tx_object_changes};
 
-  #[test]
-  fn converts_tx_object_changes() {
-    let object = unsafe {
+  fn clock_object() -> MoveObject {
+    unsafe {
       let tag = StructTag {
         address: AccountAddress::from_str("0000000000000000000000000000000000000000000000000000000000000002").unwrap(),
         module: Identifier::from_str("clock").unwrap(),
@@ -83,9 +150,11 @@ use sui_indexer::{handlers::TransactionObjectChangesToCommit, types::{IndexedObj
       ).unwrap();
 
       object
-    };
+    }
+  }
 
-    let source = TransactionObjectChangesToCommit {
+  fn clock_object_changes() -> TransactionObjectChangesToCommit {
+    TransactionObjectChangesToCommit {
         changed_objects: vec![
           IndexedObject {
             object_id: ObjectID::from_str("0x0000000000000000000000000000000000000000000000000000000000000006").unwrap(),
@@ -96,7 +165,7 @@ use sui_indexer::{handlers::TransactionObjectChangesToCommit, types::{IndexedObj
             owner_type: OwnerType::Shared,
             owner_id: None,
             object: Object::new_move(
-              object,
+              clock_object(),
               Owner::Shared {
                 initial_shared_version: SequenceNumber::from_u64(1),
               },
@@ -108,7 +177,12 @@ use sui_indexer::{handlers::TransactionObjectChangesToCommit, types::{IndexedObj
           }
         ],
         deleted_objects: vec![],
-    };
+    }
+  }
+
+  #[test]
+  fn converts_tx_object_changes() {
+    let source = clock_object_changes();
     let pb_tx_object_change = convert_tx_object_changes(&source);
     let expected = pb::TransactionObjectChange {
         changed_objects: vec![
@@ -140,6 +214,7 @@ use sui_indexer::{handlers::TransactionObjectChangesToCommit, types::{IndexedObj
                   has_public_transfer: false,
                   version: 1448001,
                   contents: vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 6, 51, 241, 16, 218, 135, 1, 0, 0],
+                  decoded: None,
                 }))
               }),
               owner: Some(pb::Owner {owner: Some(pb::owner::Owner::Shared(pb::Shared {initial_shared_version: 1}))}),
@@ -148,6 +223,7 @@ use sui_indexer::{handlers::TransactionObjectChangesToCommit, types::{IndexedObj
             }),
             coin_type: None,
             coin_balance: None,
+            df_info: None,
           }
         ],
         deleted_objects: vec![],
@@ -155,4 +231,100 @@ use sui_indexer::{handlers::TransactionObjectChangesToCommit, types::{IndexedObj
 
     assert_eq!(expected, pb_tx_object_change);
   }
+
+  #[test]
+  fn converts_dynamic_field_info() {
+    let df_info = DynamicFieldInfo {
+      name: DynamicFieldName {
+        type_: TypeTag::U64,
+        value: json!("42"),
+      },
+      bcs_name: vec![42, 0, 0, 0, 0, 0, 0, 0],
+      type_: DynamicFieldType::DynamicField,
+      object_type: "u64".to_string(),
+      object_id: ObjectID::from_str("0x0000000000000000000000000000000000000000000000000000000000000005").unwrap(),
+      version: SequenceNumber::from_u64(1448001),
+      digest: ObjectDigest::from_str("CDdzbah88YnaMJXjhpnqHy5BTo3YBAqckuD5uzfs2kyX").unwrap(),
+    };
+
+    let source = TransactionObjectChangesToCommit {
+      changed_objects: vec![
+        IndexedObject {
+          object_id: ObjectID::from_str("0x0000000000000000000000000000000000000000000000000000000000000007").unwrap(),
+          object_version: 1448001,
+          object_digest: ObjectDigest::from_str("CDdzbah88YnaMJXjhpnqHy5BTo3YBAqckuD5uzfs2kyX").unwrap(),
+          checkpoint_sequence_number: 1448000,
+          tx_digest: TransactionDigest::from_str("D7CBWgtjcgMyn1YhRZ2q7okrmiCUYW4QA5gPZT6CRa2n").unwrap(),
+          owner_type: OwnerType::Object,
+          owner_id: Some(SuiAddress::from_str("0x0000000000000000000000000000000000000000000000000000000000000005").unwrap()),
+          object: Object::new_move(
+            MoveObject::new_gas_coin(
+              SequenceNumber::from_u64(1448001),
+              ObjectID::from_str("0x0000000000000000000000000000000000000000000000000000000000000007").unwrap(),
+              1,
+            ),
+            Owner::ObjectOwner(
+              SuiAddress::from_str("0x0000000000000000000000000000000000000000000000000000000000000005").unwrap(),
+            ),
+            TransactionDigest::from_str("D7CBWgtjcgMyn1YhRZ2q7okrmiCUYW4QA5gPZT6CRa2n").unwrap(),
+          ),
+          coin_type: None,
+          coin_balance: None,
+          df_info: Some(df_info),
+        }
+      ],
+      deleted_objects: vec![],
+    };
+
+    let pb_tx_object_change = convert_tx_object_changes(&source);
+    let expected = pb::DynamicFieldInfo {
+      name: Some(pb::DynamicFieldName {
+        r#type: Some(pb::TypeTag {type_tag: Some(pb::type_tag::TypeTag::U64(()))}),
+        value: Some(pb::Value {value: Some(pb::value::Value::String("42".to_string()))}),
+      }),
+      bcs_name: vec![42, 0, 0, 0, 0, 0, 0, 0],
+      r#type: Some(pb::DynamicFieldType {
+        dynamic_field_type: Some(pb::dynamic_field_type::DynamicFieldType::DynamicField(())),
+      }),
+      object_type: "u64".to_string(),
+      object_id: Some(pb::ObjectId {
+        account_address: "0000000000000000000000000000000000000000000000000000000000000005".to_string(),
+      }),
+      version: 1448001,
+      digest: "CDdzbah88YnaMJXjhpnqHy5BTo3YBAqckuD5uzfs2kyX".to_string(),
+    };
+
+    assert_eq!(pb_tx_object_change.changed_objects[0].df_info, Some(expected));
+  }
+
+  fn decoded_contents(target: &pb::TransactionObjectChange) -> Option<pb::DecodedValue> {
+    match &target.changed_objects[0].object.as_ref().unwrap().data.as_ref().unwrap().data {
+      Some(pb::data::Data::Move(move_object)) => move_object.decoded.clone(),
+      _ => None,
+    }
+  }
+
+  #[test]
+  fn decodes_allowed_object_contents() {
+    let decoder = system_move_decoder();
+    let source = clock_object_changes();
+
+    let mut pb_tx_object_change = convert_tx_object_changes(&source);
+    decode_tx_object_changes(&decoder, &TypeFilter::default(), &source, &mut pb_tx_object_change);
+
+    let decoded = decoded_contents(&pb_tx_object_change).and_then(|decoded| decoded.decoded);
+    let Some(pb::decoded_value::Decoded::Value(value)) = decoded else {
+      panic!("expected decoded contents");
+    };
+    let Some(pb::value::Value::Object(clock)) = value.value else {
+      panic!("expected a struct");
+    };
+    assert_eq!(clock.map["timestamp_ms"].value, Some(pb::value::Value::String("1682990756147".to_string())));
+
+    let deny_clock = TypeFilter::new(&[], &["0x2::clock".to_string()]).unwrap();
+    let mut pb_tx_object_change = convert_tx_object_changes(&source);
+    decode_tx_object_changes(&decoder, &deny_clock, &source, &mut pb_tx_object_change);
+
+    assert_eq!(decoded_contents(&pb_tx_object_change), None);
+  }
 }
@@ -0,0 +1,105 @@
+use std::{fs, io::ErrorKind, path::PathBuf};
+use eyre::{ensure, Result, WrapErr};
+use serde::{Deserialize, Serialize};
+use tokio::task::spawn_blocking;
+use crate::atomic_file::write_atomically;
+
+/// Checkpoint the stream starts from when neither an explicit start nor a cursor is available
+pub const DEFAULT_STARTING_CHECKPOINT_SEQ: u64 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cursor {
+  pub chain_id: String,
+  /// The last checkpoint that was fully emitted and flushed to the sink
+  pub checkpoint_seq: u64,
+}
+
+/// Stores the cursor on disk so the streamer can pick up where it left off after a restart
+pub struct CursorFile {
+  path: PathBuf,
+}
+
+impl CursorFile {
+  pub fn new(path: impl Into<PathBuf>) -> Self {
+    Self {path: path.into()}
+  }
+
+  pub fn load(&self) -> Result<Option<Cursor>> {
+    let content = match fs::read(&self.path) {
+      Ok(content) => content,
+      Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+      Err(e) => return Err(e).wrap_err_with(|| format!("could not read cursor file {}", self.path.display())),
+    };
+
+    let cursor = serde_json::from_slice(&content)
+    .wrap_err_with(|| format!("could not parse cursor file {}", self.path.display()))?;
+
+    Ok(Some(cursor))
+  }
+
+  /// A crash half way through can never leave a truncated cursor behind
+  pub async fn save(&self, cursor: &Cursor) -> Result<()> {
+    let path = self.path.clone();
+    let content = serde_json::to_vec(cursor)?;
+
+    spawn_blocking(move || write_atomically(&path, &content)).await?
+  }
+}
+
+/// An explicit start always wins. Otherwise we resume right after the checkpoint stored in the cursor file
+/// as long as it was written for the same chain.
+pub fn resolve_starting_checkpoint_seq(
+  cursor_file: Option<&CursorFile>,
+  chain_id: &str,
+  starting_checkpoint_seq: Option<u64>,
+) -> Result<u64> {
+  if let Some(starting_checkpoint_seq) = starting_checkpoint_seq {
+    return Ok(starting_checkpoint_seq)
+  }
+
+  let Some(cursor) = cursor_file.map(CursorFile::load).transpose()?.flatten() else {
+    return Ok(DEFAULT_STARTING_CHECKPOINT_SEQ)
+  };
+
+  ensure!(
+    cursor.chain_id == chain_id,
+    "cursor was written for chain {} but the streamer is configured for chain {}",
+    cursor.chain_id,
+    chain_id,
+  );
+
+  Ok(cursor.checkpoint_seq + 1)
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::test_utils::TempDir;
+  use super::{resolve_starting_checkpoint_seq, Cursor, CursorFile, DEFAULT_STARTING_CHECKPOINT_SEQ};
+
+  fn cursor_file(temp_dir: &TempDir) -> CursorFile {
+    CursorFile::new(temp_dir.path().join("cursor.json"))
+  }
+
+  #[tokio::test]
+  async fn resumes_after_stored_checkpoint() {
+    let temp_dir = TempDir::new("cursor-resume");
+    let cursor_file = cursor_file(&temp_dir);
+    assert_eq!(
+      resolve_starting_checkpoint_seq(Some(&cursor_file), "chain", None).unwrap(),
+      DEFAULT_STARTING_CHECKPOINT_SEQ,
+    );
+
+    cursor_file.save(&Cursor {chain_id: "chain".to_string(), checkpoint_seq: 10}).await.unwrap();
+    assert_eq!(resolve_starting_checkpoint_seq(Some(&cursor_file), "chain", None).unwrap(), 11);
+    assert_eq!(resolve_starting_checkpoint_seq(Some(&cursor_file), "chain", Some(5)).unwrap(), 5);
+  }
+
+  #[tokio::test]
+  async fn refuses_to_resume_on_a_different_chain() {
+    let temp_dir = TempDir::new("cursor-chain-mismatch");
+    let cursor_file = cursor_file(&temp_dir);
+    cursor_file.save(&Cursor {chain_id: "chain".to_string(), checkpoint_seq: 10}).await.unwrap();
+
+    assert!(resolve_starting_checkpoint_seq(Some(&cursor_file), "other_chain", None).is_err());
+  }
+}
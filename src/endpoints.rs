@@ -0,0 +1,290 @@
+use std::{future::Future, sync::{Arc, Mutex}, time::Duration};
+use async_trait::async_trait;
+use eyre::{ensure, eyre, Result};
+use log::warn;
+use sui_rest_api::Client;
+use sui_types::full_checkpoint_content::CheckpointData;
+use tokio::{sync::{Semaphore, SemaphorePermit}, time::{timeout, Instant}};
+use crate::{checkpoint_source::{CheckpointSource, FetchError}, rate_limiter::RateLimiter};
+
+/// How much weight the latest request gets in the moving averages
+const EWMA_WEIGHT: f64 = 0.2;
+/// Seconds of latency an endpoint that fails every request is penalized with
+const ERROR_PENALTY_SECS: f64 = 5.0;
+/// A request that takes longer than this counts as failed so that a stalled endpoint cannot stall the stream
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const MIN_COOLDOWN: Duration = Duration::from_millis(500);
+const MAX_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Health of an endpoint based on the outcome of its recent requests
+#[derive(Debug, Clone, Copy, Default)]
+struct Health {
+  /// None until the first request succeeds
+  latency: Option<Duration>,
+  error_rate: f64,
+  consecutive_errors: u32,
+  /// Endpoints that keep failing are skipped for a while
+  cooldown_until: Option<Instant>,
+}
+
+impl Health {
+  fn record_success(&mut self, latency: Duration) {
+    self.latency = Some(match self.latency {
+      Some(avg) => avg.mul_f64(1.0 - EWMA_WEIGHT) + latency.mul_f64(EWMA_WEIGHT),
+      None => latency,
+    });
+    self.error_rate *= 1.0 - EWMA_WEIGHT;
+    self.consecutive_errors = 0;
+    self.cooldown_until = None;
+  }
+
+  fn record_failure(&mut self, now: Instant) {
+    self.error_rate = self.error_rate * (1.0 - EWMA_WEIGHT) + EWMA_WEIGHT;
+    self.consecutive_errors += 1;
+
+    let cooldown = MIN_COOLDOWN.saturating_mul(1 << (self.consecutive_errors - 1).min(8)).min(MAX_COOLDOWN);
+    self.cooldown_until = Some(now + cooldown);
+  }
+
+  /// Lower is better. Endpoints that have not been measured yet score zero so that they get tried.
+  fn score(&self) -> f64 {
+    self.latency.unwrap_or_default().as_secs_f64() + ERROR_PENALTY_SECS * self.error_rate
+  }
+
+  fn is_cooling_down(&self, now: Instant) -> bool {
+    self.cooldown_until.map_or(false, |cooldown_until| now < cooldown_until)
+  }
+}
+
+/// How hard we are allowed to hit the endpoints, across all of them
+#[derive(Debug, Clone, Copy)]
+pub struct RequestLimits {
+  pub max_in_flight_requests: usize,
+  /// No limit if not set
+  pub max_requests_per_sec: Option<u32>,
+}
+
+impl Default for RequestLimits {
+  fn default() -> Self {
+    Self {
+      max_in_flight_requests: 10,
+      max_requests_per_sec: None,
+    }
+  }
+}
+
+/// Enforces the request limits. Clones share the same limits.
+#[derive(Clone)]
+pub struct RequestGate {
+  in_flight: Arc<Semaphore>,
+  max_in_flight_requests: usize,
+  rate_limiter: Option<RateLimiter>,
+}
+
+impl RequestGate {
+  pub fn new(limits: RequestLimits) -> Result<Self> {
+    ensure!(limits.max_in_flight_requests > 0, "at least one request must be allowed in flight");
+
+    Ok(Self {
+      in_flight: Arc::new(Semaphore::new(limits.max_in_flight_requests)),
+      max_in_flight_requests: limits.max_in_flight_requests,
+      rate_limiter: limits.max_requests_per_sec.map(RateLimiter::new),
+    })
+  }
+
+  /// Waits until the limits allow another request. It counts as in flight until the permit is dropped.
+  pub async fn acquire(&self) -> Result<SemaphorePermit<'_>> {
+    let permit = self.in_flight.acquire().await?;
+    if let Some(rate_limiter) = &self.rate_limiter {
+      rate_limiter.acquire().await;
+    }
+
+    Ok(permit)
+  }
+
+  pub fn max_in_flight_requests(&self) -> usize {
+    self.max_in_flight_requests
+  }
+}
+
+pub struct Endpoint {
+  pub url: String,
+  pub client: Client,
+  health: Mutex<Health>,
+}
+
+impl Endpoint {
+  fn new(url: &str) -> Self {
+    Self {
+      url: url.to_string(),
+      client: Client::new(&format!("{}/rest", url)),
+      health: Mutex::new(Health::default()),
+    }
+  }
+
+  fn health(&self) -> Health {
+    *self.health.lock().unwrap()
+  }
+}
+
+/// A set of RPC endpoints serving the same chain. Every request goes to the healthiest endpoint at the time it is
+/// made which means retrying a failed request naturally fails over to another endpoint.
+#[derive(Clone)]
+pub struct EndpointPool {
+  endpoints: Arc<Vec<Endpoint>>,
+  request_gate: RequestGate,
+}
+
+impl EndpointPool {
+  pub fn new(urls: &[String], limits: RequestLimits) -> Result<Self> {
+    ensure!(!urls.is_empty(), "at least one rpc endpoint is required");
+
+    let endpoints = urls.iter().map(|url| Endpoint::new(url)).collect();
+
+    Ok(Self {
+      endpoints: Arc::new(endpoints),
+      request_gate: RequestGate::new(limits)?,
+    })
+  }
+
+  /// Anything else sending requests to the same endpoints should go through this so that the limits hold overall
+  pub fn request_gate(&self) -> &RequestGate {
+    &self.request_gate
+  }
+
+  /// The endpoint with the lowest score among those that are not cooling down. If all of them are, the one that
+  /// comes back first.
+  fn pick(&self) -> &Endpoint {
+    let now = Instant::now();
+    let healths = self.endpoints.iter().map(|endpoint| (endpoint, endpoint.health())).collect::<Vec<_>>();
+
+    let available = healths.iter()
+    .filter(|(_, health)| !health.is_cooling_down(now))
+    .min_by(|(_, a), (_, b)| a.score().total_cmp(&b.score()));
+
+    let (endpoint, _) = *available.unwrap_or_else(|| {
+      healths.iter().min_by_key(|(_, health)| health.cooldown_until).expect("pool is never empty")
+    });
+
+    endpoint
+  }
+
+  /// Sends the request to the healthiest endpoint, once the request limits allow it, and updates the health of the
+  /// endpoint with the outcome
+  pub async fn call<'a, T, F, Fut>(&'a self, request: F) -> Result<T>
+  where
+    F: FnOnce(&'a Endpoint) -> Fut,
+    Fut: Future<Output = Result<T>> + 'a,
+  {
+    let _permit = self.request_gate.acquire().await?;
+
+    // Picked only once we are allowed to send so the choice reflects the latest health
+    let endpoint = self.pick();
+    let started_at = Instant::now();
+
+    let result = match timeout(REQUEST_TIMEOUT, request(endpoint)).await {
+      Ok(result) => result,
+      Err(_) => Err(eyre!("request timed out after {:?}", REQUEST_TIMEOUT)),
+    };
+
+    let mut health = endpoint.health.lock().unwrap();
+    match &result {
+      Ok(_) => health.record_success(started_at.elapsed()),
+      Err(err) => {
+        health.record_failure(Instant::now());
+        warn!("Request to {} failed: {}", endpoint.url, err);
+      },
+    }
+
+    result
+  }
+}
+
+/// Every request goes to the healthiest endpoint at the time so a failing endpoint is abandoned mid-stream
+/// without skipping any checkpoint
+#[async_trait]
+impl CheckpointSource for EndpointPool {
+  async fn latest_checkpoint(&self) -> Result<u64> {
+    self.call(|endpoint| async move {
+      let checkpoint = endpoint.client.get_latest_checkpoint().await
+      .map_err(|err| eyre!("could not get the latest checkpoint from {}: {}", endpoint.url, err))?;
+
+      Ok(checkpoint.sequence_number)
+    }).await
+  }
+
+  /// The REST client doesn't tell a bad response from a failed request so every failure is transient
+  async fn fetch_checkpoint(&self, seq: u64) -> Result<CheckpointData, FetchError> {
+    let checkpoint = self.call(|endpoint| async move {
+      endpoint.client.get_full_checkpoint(seq).await
+      .map_err(|err| eyre!("could not download checkpoint {} from {}: {}", seq, endpoint.url, err))
+    }).await?;
+
+    Ok(checkpoint)
+  }
+
+  /// There is no point in having more downloads pending than this since the rest would only wait for a permit
+  fn max_concurrent_fetches(&self) -> usize {
+    self.request_gate.max_in_flight_requests()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{convert::Infallible, net::SocketAddr, time::Duration};
+  use eyre::{ensure, Result};
+  use hyper::{service::{make_service_fn, service_fn}, Body, Request, Response, Server, StatusCode};
+  use super::{EndpointPool, RequestLimits};
+
+  /// Starts a stand-in server that answers every request with the given status after the given delay
+  fn serve(status: StatusCode, delay: Duration) -> String {
+    let make_service = make_service_fn(move |_: Request<Body>| async move {
+      Ok::<_, Infallible>(service_fn(move |_: Request<Body>| async move {
+        tokio::time::sleep(delay).await;
+
+        Ok::<_, Infallible>(Response::builder().status(status).body(Body::empty()).unwrap())
+      }))
+    });
+
+    let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+    let url = format!("http://{}", server.local_addr());
+    tokio::spawn(server);
+
+    url
+  }
+
+  async fn get(url: &str) -> Result<()> {
+    let response = hyper::Client::new().get(url.parse()?).await?;
+    ensure!(response.status().is_success(), "{} responded with {}", url, response.status());
+
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn fails_over_to_a_healthy_endpoint() {
+    let down = serve(StatusCode::INTERNAL_SERVER_ERROR, Duration::ZERO);
+    let up = serve(StatusCode::OK, Duration::ZERO);
+    let pool = EndpointPool::new(&[down.clone(), up.clone()], RequestLimits::default()).unwrap();
+
+    // The first request goes to the endpoint that is down, every request after that to the one that is up
+    assert!(pool.call(|endpoint| get(&endpoint.url)).await.is_err());
+    for _ in 0..5 {
+      pool.call(|endpoint| get(&endpoint.url)).await.unwrap();
+    }
+
+    assert_eq!(pool.pick().url, up);
+  }
+
+  #[tokio::test]
+  async fn prefers_the_fastest_endpoint() {
+    let slow = serve(StatusCode::OK, Duration::from_millis(200));
+    let fast = serve(StatusCode::OK, Duration::ZERO);
+    let pool = EndpointPool::new(&[slow, fast.clone()], RequestLimits::default()).unwrap();
+
+    for _ in 0..5 {
+      pool.call(|endpoint| get(&endpoint.url)).await.unwrap();
+    }
+
+    assert_eq!(pool.pick().url, fast);
+  }
+}
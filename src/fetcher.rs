@@ -0,0 +1,203 @@
+use std::{ops::RangeInclusive, sync::Arc, time::Duration};
+use backoff::{future::retry_notify, ExponentialBackoff};
+use eyre::{eyre, Report, Result};
+use futures::{stream, StreamExt};
+use log::{info, warn};
+use mysten_metrics::metered_channel::Sender;
+use sui_indexer::framework::fetcher::CheckpointDownloadData;
+use tokio::sync::watch;
+use crate::checkpoint_source::{CheckpointSource, FetchError};
+
+/// How long a checkpoint that is known to exist is retried before the fetcher fails. The pipeline is then restarted
+/// which gives up after --max-consecutive-failures, so a source that keeps failing surfaces as an error rather than
+/// a stalled stream
+const MAX_FETCH_RETRY_TIME: Duration = Duration::from_secs(300);
+
+/// Downloads a single checkpoint, retrying transient failures with the given backoff policy. Permanent failures
+/// are returned right away.
+pub async fn download_checkpoint(
+  source: &dyn CheckpointSource,
+  seq: u64,
+  backoff: ExponentialBackoff,
+) -> Result<CheckpointDownloadData> {
+  let download = || async {
+    let data = source.fetch_checkpoint(seq).await.map_err(|err| match err {
+      FetchError::Transient(err) => backoff::Error::transient(err),
+      FetchError::Permanent(err) => backoff::Error::permanent(err),
+    })?;
+    let size = bcs::serialized_size(&data).map_err(|err| backoff::Error::permanent(eyre!("{}", err)))?;
+
+    Ok(CheckpointDownloadData {size, data})
+  };
+  let notify = |err: Report, delay: Duration| {
+    warn!("Could not fetch checkpoint {}, retrying in {:?}: {:?}", seq, delay, err);
+  };
+
+  retry_notify(backoff, download, notify).await
+}
+
+/// Keeps a fetcher from running more than `size` checkpoints ahead of the next checkpoint to be emitted
+#[derive(Clone)]
+pub struct Window {
+  pub next_seq: watch::Receiver<u64>,
+  pub size: u64,
+}
+
+impl Window {
+  async fn wait_for(&mut self, seq: u64) {
+    let size = self.size;
+    // An error means the commit loop is gone in which case there is nothing left to wait for
+    let _ = self.next_seq.wait_for(|next_seq| seq < next_seq + size).await;
+  }
+}
+
+/// Downloads the checkpoints concurrently and sends them in order. Returns false once nobody is listening anymore
+/// and fails if a checkpoint can never be fetched.
+async fn fetch_in_order(
+  source: &dyn CheckpointSource,
+  seqs: impl Iterator<Item = u64>,
+  window: Option<&Window>,
+  sender: &Sender<CheckpointDownloadData>,
+) -> Result<bool> {
+  // Checkpoints we know about must exist so they are retried for a long while, but not forever
+  let backoff = ExponentialBackoff {
+    max_elapsed_time: Some(MAX_FETCH_RETRY_TIME),
+    ..Default::default()
+  };
+
+  let mut downloads = stream::iter(seqs)
+  .map(|seq| {
+    let backoff = backoff.clone();
+    let window = window.cloned();
+
+    async move {
+      if let Some(mut window) = window {
+        window.wait_for(seq).await;
+      }
+
+      download_checkpoint(source, seq, backoff).await
+    }
+  })
+  .buffered(source.max_concurrent_fetches().max(1));
+
+  while let Some(checkpoint_download_data) = downloads.next().await {
+    if sender.send(checkpoint_download_data?).await.is_err() {
+      return Ok(false)
+    }
+  }
+
+  Ok(true)
+}
+
+/// Unlike the tail fetcher which keeps polling for new checkpoints forever, this one downloads a bounded set of
+/// ranges in order and stops once the last checkpoint has been sent
+pub struct RangeFetcher {
+  source: Arc<dyn CheckpointSource>,
+  ranges: Vec<RangeInclusive<u64>>,
+  window: Option<Window>,
+  sender: Sender<CheckpointDownloadData>,
+}
+
+impl RangeFetcher {
+  pub fn new(
+    source: Arc<dyn CheckpointSource>,
+    ranges: Vec<RangeInclusive<u64>>,
+    window: Option<Window>,
+    sender: Sender<CheckpointDownloadData>,
+  ) -> Self {
+    Self {source, ranges, window, sender}
+  }
+
+  pub async fn run(self) -> Result<()> {
+    let seqs = self.ranges.clone().into_iter().flatten();
+
+    if fetch_in_order(&*self.source, seqs, self.window.as_ref(), &self.sender).await? {
+      info!("Fetched all checkpoints in {:?}", self.ranges);
+    }
+
+    Ok(())
+  }
+}
+
+/// Follows the tip of the chain. It keeps asking the source for the latest checkpoint and downloads everything up
+/// to it in order. Once it has caught up it waits for the source to report new checkpoints.
+pub struct TailFetcher {
+  source: Arc<dyn CheckpointSource>,
+  next_seq: u64,
+  sender: Sender<CheckpointDownloadData>,
+}
+
+impl TailFetcher {
+  pub fn new(source: Arc<dyn CheckpointSource>, next_seq: u64, sender: Sender<CheckpointDownloadData>) -> Self {
+    Self {source, next_seq, sender}
+  }
+
+  pub async fn run(mut self) -> Result<()> {
+    loop {
+      let latest = match self.source.latest_checkpoint().await {
+        Ok(latest) => latest,
+        Err(err) => {
+          warn!("{:?}", err);
+          self.source.wait_for_new_checkpoints().await;
+          continue;
+        },
+      };
+
+      if latest < self.next_seq {
+        self.source.wait_for_new_checkpoints().await;
+        continue;
+      }
+
+      if !fetch_in_order(&*self.source, self.next_seq..=latest, None, &self.sender).await? {
+        return Ok(())
+      }
+
+      self.next_seq = latest + 1;
+    }
+  }
+}
+
+/// Splits the range into consecutive chunks and deals them out to the workers round robin. Interleaving the
+/// chunks rather than giving each worker one contiguous slice keeps all workers busy close to the head of the
+/// stream which is what allows the reorder window to stay small.
+pub fn split_range(range: RangeInclusive<u64>, workers: usize, chunk_size: u64) -> Vec<Vec<RangeInclusive<u64>>> {
+  let workers = workers.max(1);
+  let chunk_size = chunk_size.max(1);
+  let mut worker_ranges = vec![vec![]; workers];
+
+  let (mut chunk_start, end) = (*range.start(), *range.end());
+  let mut worker = 0;
+
+  while chunk_start <= end {
+    let chunk_end = chunk_start.saturating_add(chunk_size - 1).min(end);
+    worker_ranges[worker].push(chunk_start..=chunk_end);
+
+    if chunk_end == u64::MAX {
+      break
+    }
+
+    chunk_start = chunk_end + 1;
+    worker = (worker + 1) % workers;
+  }
+
+  worker_ranges.retain(|ranges| !ranges.is_empty());
+  worker_ranges
+}
+
+#[cfg(test)]
+mod tests {
+  use super::split_range;
+
+  #[test]
+  fn splits_range_round_robin() {
+    assert_eq!(split_range(1..=10, 2, 3), vec![
+      vec![1..=3, 7..=9],
+      vec![4..=6, 10..=10],
+    ]);
+  }
+
+  #[test]
+  fn drops_idle_workers() {
+    assert_eq!(split_range(1..=2, 4, 5), vec![vec![1..=2]]);
+  }
+}
@@ -0,0 +1,278 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use async_trait::async_trait;
+use eyre::{ensure, eyre, Result};
+use log::warn;
+use roaring::RoaringBitmap;
+use sui_json_rpc_types::{
+  CheckpointId, SuiEvent, SuiGetPastObjectRequest, SuiObjectDataOptions, SuiPastObjectResponse,
+  SuiTransactionBlockResponse, SuiTransactionBlockResponseOptions,
+};
+use sui_sdk::{SuiClient, SuiClientBuilder};
+use sui_types::{
+  base_types::{ExecutionDigests, ObjectID, SequenceNumber},
+  crypto::AuthorityStrongQuorumSignInfo,
+  digests::TransactionDigest,
+  effects::{TransactionEffects, TransactionEffectsAPI, TransactionEvents},
+  event::Event,
+  full_checkpoint_content::{CheckpointData, CheckpointTransaction},
+  message_envelope::Message,
+  messages_checkpoint::{CertifiedCheckpointSummary, CheckpointContents, CheckpointSummary},
+  object::Object,
+  transaction::{SenderSignedData, Transaction},
+};
+use crate::{checkpoint_source::{CheckpointSource, FetchError}, endpoints::RequestGate};
+
+/// The JSON-RPC API caps how many transactions or objects can be asked for in a single request
+const MULTI_GET_CHUNK_SIZE: usize = 50;
+
+/// Rebuilds the checkpoints the REST API serves out of the standard JSON-RPC methods, for nodes and providers that
+/// don't expose the experimental REST API. Each checkpoint takes one request for the checkpoint itself plus one
+/// per 50 transactions and one per 50 objects they read or wrote, each of which counts toward the request limits.
+pub struct JsonRpcSource {
+  clients: Vec<(String, SuiClient)>,
+  next_client: AtomicUsize,
+  request_gate: RequestGate,
+}
+
+impl JsonRpcSource {
+  /// Endpoints that can't be reached are left out
+  pub async fn connect(urls: &[String], request_gate: RequestGate) -> Result<Self> {
+    let mut clients = vec![];
+
+    for url in urls {
+      let _permit = request_gate.acquire().await?;
+      match SuiClientBuilder::default().build(url).await {
+        Ok(client) => clients.push((url.clone(), client)),
+        Err(err) => warn!("Could not connect to the JSON-RPC API of {}: {}", url, err),
+      }
+    }
+
+    ensure!(!clients.is_empty(), "none of the rpc endpoints serves the JSON-RPC API");
+
+    Ok(Self {clients, next_client: AtomicUsize::new(0), request_gate})
+  }
+
+  /// Requests go to the endpoints round robin which means a retried request goes to another endpoint
+  fn client(&self) -> &(String, SuiClient) {
+    let index = self.next_client.fetch_add(1, Ordering::Relaxed) % self.clients.len();
+    &self.clients[index]
+  }
+
+  async fn fetch_transactions(&self, digests: &[TransactionDigest]) -> Result<Vec<SuiTransactionBlockResponse>> {
+    let options = SuiTransactionBlockResponseOptions {
+      show_raw_input: true,
+      show_raw_effects: true,
+      show_events: true,
+      ..Default::default()
+    };
+    let mut transactions = Vec::with_capacity(digests.len());
+
+    for chunk in digests.chunks(MULTI_GET_CHUNK_SIZE) {
+      let _permit = self.request_gate.acquire().await?;
+      let (url, client) = self.client();
+      let responses = client.read_api().multi_get_transactions_with_options(chunk.to_vec(), options.clone()).await
+      .map_err(|err| eyre!("could not get transactions from {}: {}", url, err))?;
+      ensure!(responses.len() == chunk.len(), "{} returned {} of {} transactions", url, responses.len(), chunk.len());
+
+      transactions.extend(responses);
+    }
+
+    Ok(transactions)
+  }
+
+  /// Objects are fetched at the version the transaction read or wrote them rather than at their latest version. An
+  /// object version that has been pruned or that can't be rebuilt is a permanent failure.
+  async fn fetch_objects(&self, object_versions: &[(ObjectID, SequenceNumber)]) -> Result<Vec<Object>, FetchError> {
+    let mut objects = Vec::with_capacity(object_versions.len());
+
+    for chunk in object_versions.chunks(MULTI_GET_CHUNK_SIZE) {
+      let requests = chunk.iter()
+      .map(|(object_id, version)| SuiGetPastObjectRequest {object_id: *object_id, version: *version})
+      .collect();
+
+      let _permit = self.request_gate.acquire().await?;
+      let (url, client) = self.client();
+      let responses = client.read_api()
+      .try_multi_get_parsed_past_object(requests, SuiObjectDataOptions::bcs_lossless()).await
+      .map_err(|err| eyre!("could not get objects from {}: {}", url, err))?;
+      if responses.len() != chunk.len() {
+        return Err(eyre!("{} returned {} of {} objects", url, responses.len(), chunk.len()).into())
+      }
+
+      for (response, (object_id, version)) in responses.into_iter().zip(chunk) {
+        // The endpoint has pruned that version, or never had it, so asking again is not going to bring it back
+        let SuiPastObjectResponse::VersionFound(object_data) = response else {
+          return Err(FetchError::Permanent(eyre!(
+            "{} does not have object {} at version {}: {:?}", url, object_id, version, response,
+          )))
+        };
+        let object: Object = object_data.try_into()
+        .map_err(|err| eyre!("could not rebuild object {} at version {}: {}", object_id, version, err))
+        .map_err(FetchError::Permanent)?;
+
+        objects.push(object);
+      }
+    }
+
+    Ok(objects)
+  }
+}
+
+#[async_trait]
+impl CheckpointSource for JsonRpcSource {
+  async fn latest_checkpoint(&self) -> Result<u64> {
+    let _permit = self.request_gate.acquire().await?;
+    let (url, client) = self.client();
+
+    client.read_api().get_latest_checkpoint_sequence_number().await
+    .map_err(|err| eyre!("could not get the latest checkpoint from {}: {}", url, err))
+  }
+
+  /// Responses that can't be turned back into the certified checkpoint are permanent failures, everything else is
+  /// retried
+  async fn fetch_checkpoint(&self, seq: u64) -> Result<CheckpointData, FetchError> {
+    let (url, client) = self.client();
+    let checkpoint = {
+      let _permit = self.request_gate.acquire().await?;
+      client.read_api().get_checkpoint(CheckpointId::SequenceNumber(seq)).await
+      .map_err(|err| eyre!("could not get checkpoint {} from {}: {}", seq, url, err))?
+    };
+
+    let mut transactions = vec![];
+    for response in self.fetch_transactions(&checkpoint.transactions).await? {
+      transactions.push(convert_transaction(response).map_err(FetchError::Permanent)?);
+    }
+
+    // Inputs and outputs of every transaction are fetched at once and handed back out in the same order
+    let mut object_versions = vec![];
+    for (_, effects, _) in &transactions {
+      object_versions.extend(effects.modified_at_versions());
+      object_versions.extend(output_versions(effects));
+    }
+    let mut objects = self.fetch_objects(&object_versions).await?.into_iter();
+
+    let checkpoint_contents = CheckpointContents::new_with_digests_and_signatures(
+      transactions.iter()
+      .map(|(transaction, effects, _)| ExecutionDigests::new(*transaction.digest(), effects.digest())),
+      transactions.iter().map(|(transaction, _, _)| transaction.data().tx_signatures().to_vec()).collect(),
+    );
+
+    let summary = CheckpointSummary {
+      epoch: checkpoint.epoch,
+      sequence_number: checkpoint.sequence_number,
+      network_total_transactions: checkpoint.network_total_transactions,
+      content_digest: *checkpoint_contents.digest(),
+      previous_digest: checkpoint.previous_digest,
+      epoch_rolling_gas_cost_summary: checkpoint.epoch_rolling_gas_cost_summary,
+      end_of_epoch_data: checkpoint.end_of_epoch_data,
+      timestamp_ms: checkpoint.timestamp_ms,
+      checkpoint_commitments: checkpoint.checkpoint_commitments,
+      // Not exposed by the JSON-RPC API. It is empty for every protocol version this extractor supports and the
+      // digest check below fails the checkpoint permanently if it ever isn't.
+      version_specific_data: vec![],
+    };
+    // Anything the JSON-RPC API doesn't expose but is part of the checkpoint would change its digest so this makes
+    // sure the rebuilt checkpoint is exactly the one that was certified
+    if summary.digest() != checkpoint.digest {
+      return Err(FetchError::Permanent(eyre!(
+        "checkpoint {} rebuilt from {} has digest {} instead of {}",
+        seq,
+        url,
+        summary.digest(),
+        checkpoint.digest,
+      )))
+    }
+
+    // The JSON-RPC API only exposes the aggregated signature and not which validators signed. The signers are not
+    // part of the checkpoint digest nor of the emitted checkpoint, which only carries the aggregated signature, so
+    // they are left out here rather than made up. It does mean the certificate can't be verified against the
+    // committee, including in recordings made with --record.
+    let signature = AuthorityStrongQuorumSignInfo {
+      epoch: checkpoint.epoch,
+      signature: checkpoint.validator_signature,
+      signers_map: RoaringBitmap::new(),
+    };
+    let checkpoint_summary = CertifiedCheckpointSummary::new_from_data_and_sig(summary, signature);
+
+    let transactions = transactions.into_iter()
+    .map(|(transaction, effects, events)| {
+      let input_objects = objects.by_ref().take(effects.modified_at_versions().len()).collect();
+      let output_objects = objects.by_ref().take(output_versions(&effects).count()).collect();
+
+      CheckpointTransaction {transaction, effects, events, input_objects, output_objects}
+    })
+    .collect();
+
+    Ok(CheckpointData {checkpoint_summary, checkpoint_contents, transactions})
+  }
+
+  fn max_concurrent_fetches(&self) -> usize {
+    self.request_gate.max_in_flight_requests()
+  }
+}
+
+/// Versions of the objects that the transaction created, mutated or unwrapped
+fn output_versions(effects: &TransactionEffects) -> impl Iterator<Item = (ObjectID, SequenceNumber)> {
+  effects.all_changed_objects().into_iter().map(|((object_id, version, _), _, _)| (object_id, version))
+}
+
+fn convert_transaction(
+  response: SuiTransactionBlockResponse,
+) -> Result<(Transaction, TransactionEffects, Option<TransactionEvents>)> {
+  let sender_signed_data = bcs::from_bytes::<SenderSignedData>(&response.raw_transaction)
+  .map_err(|err| eyre!("could not decode transaction {}: {}", response.digest, err))?;
+  let effects = bcs::from_bytes::<TransactionEffects>(&response.raw_effects)
+  .map_err(|err| eyre!("could not decode the effects of transaction {}: {}", response.digest, err))?;
+
+  // Transactions that didn't emit any event have no events at all rather than an empty list
+  let events = effects.events_digest().map(|_| TransactionEvents {
+    data: response.events.map(|events| events.data).unwrap_or_default().into_iter().map(convert_event).collect(),
+  });
+
+  Ok((Transaction::new(sender_signed_data), effects, events))
+}
+
+fn convert_event(source: SuiEvent) -> Event {
+  Event {
+    package_id: source.package_id,
+    transaction_module: source.transaction_module,
+    sender: source.sender,
+    type_: source.type_,
+    contents: source.bcs,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::str::FromStr;
+  use move_core_types::{identifier::Identifier, language_storage::StructTag};
+  use sui_json_rpc_types::SuiEvent;
+  use sui_types::{base_types::{ObjectID, SuiAddress}, digests::TransactionDigest, event::{Event, EventID}};
+  use super::convert_event;
+
+  #[test]
+  fn converts_events_back_to_their_raw_form() {
+    let package_id = ObjectID::from_str("0xdee9").unwrap();
+    let sender = SuiAddress::from_str("0x0000000000000000000000000000000000000000000000000000000000000006").unwrap();
+    let type_ = StructTag::from_str("0xdee9::clob_v2::OrderPlaced<0x2::sui::SUI>").unwrap();
+
+    let source = SuiEvent {
+      id: EventID {tx_digest: TransactionDigest::ZERO, event_seq: 0},
+      package_id,
+      transaction_module: Identifier::new("clob_v2").unwrap(),
+      sender,
+      type_: type_.clone(),
+      parsed_json: serde_json::json!({"order_id": "1"}),
+      bcs: vec![1, 2, 3],
+      timestamp_ms: None,
+    };
+
+    assert_eq!(convert_event(source), Event {
+      package_id,
+      transaction_module: Identifier::new("clob_v2").unwrap(),
+      sender,
+      type_,
+      contents: vec![1, 2, 3],
+    });
+  }
+}
@@ -5,3 +5,17 @@ pub mod pb;
 pub mod process_manager;
 pub mod args;
 pub mod logger;
+pub mod cursor;
+pub mod atomic_file;
+pub mod reorder_buffer;
+pub mod fetcher;
+pub mod checkpoint_dir;
+pub mod checkpoint_source;
+pub mod json_rpc_source;
+pub mod supervisor;
+pub mod endpoints;
+pub mod rate_limiter;
+pub mod batch_size;
+pub mod type_filter;
+#[cfg(test)]
+mod test_utils;
@@ -1,11 +1,52 @@
-pub trait Logger {
-  fn log(&self, msg: &str);
+use async_trait::async_trait;
+use eyre::Result;
+use tokio::io::{stdout, AsyncWriteExt, BufWriter, Stdout};
+
+/// Size of the in-memory buffer that sits in front of stdout. Writes only hit the pipe once it fills up
+/// or when the streamer flushes at the end of a block.
+const STDOUT_BUFFER_SIZE: usize = 1024 * 1024;
+
+#[async_trait]
+pub trait Logger: Send {
+  /// Queues a single line. An error means the sink can no longer be written to.
+  async fn log(&mut self, msg: &str) -> Result<()>;
+
+  /// Called at block boundaries. Once it returns Ok, everything logged so far has reached the sink.
+  async fn flush(&mut self) -> Result<()> {
+    Ok(())
+  }
 }
 
-pub struct StdoutLogger;
+pub struct StdoutLogger {
+  writer: BufWriter<Stdout>,
+}
+
+impl StdoutLogger {
+  pub fn new() -> Self {
+    Self {
+      writer: BufWriter::with_capacity(STDOUT_BUFFER_SIZE, stdout()),
+    }
+  }
+}
 
+impl Default for StdoutLogger {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[async_trait]
 impl Logger for StdoutLogger {
-  fn log(&self, msg: &str) {
-    println!("{}", msg);
+  async fn log(&mut self, msg: &str) -> Result<()> {
+    self.writer.write_all(msg.as_bytes()).await?;
+    self.writer.write_all(b"\n").await?;
+
+    Ok(())
+  }
+
+  async fn flush(&mut self) -> Result<()> {
+    self.writer.flush().await?;
+
+    Ok(())
   }
 }
@@ -10,7 +10,7 @@ async fn main() -> Result<()> {
   let args = Args::parse();
   let pm = ProcessManager::new(args);
 
-  let stdout_logger = StdoutLogger {};
+  let stdout_logger = StdoutLogger::new();
   pm.start(stdout_logger).await;
 
   Ok(())
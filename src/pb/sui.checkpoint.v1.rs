@@ -14,8 +14,11 @@ pub struct CheckpointData {
     pub tx_indices: ::prost::alloc::vec::Vec<TxIndex>,
     #[prost(message, repeated, tag = "6")]
     pub display_updates: ::prost::alloc::vec::Vec<StoredDisplay>,
-    #[prost(message, optional, tag = "7")]
-    pub packages: ::core::option::Option<IndexedPackage>,
+    #[prost(message, repeated, tag = "7")]
+    pub packages: ::prost::alloc::vec::Vec<IndexedPackage>,
+    /// Only set on the checkpoint that closes an epoch and on the genesis checkpoint
+    #[prost(message, optional, tag = "8")]
+    pub epoch: ::core::option::Option<Epoch>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -129,6 +132,9 @@ pub struct Event {
     pub r#type: ::core::option::Option<StructTag>,
     #[prost(bytes = "vec", tag = "5")]
     pub contents: ::prost::alloc::vec::Vec<u8>,
+    /// Only set when running with --decode-events
+    #[prost(message, optional, tag = "6")]
+    pub decoded: ::core::option::Option<DecodedValue>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -260,6 +266,122 @@ pub struct Created {
 pub struct SenderSignedTransaction {
     #[prost(message, optional, tag = "1")]
     pub intent_message: ::core::option::Option<IntentMessage>,
+    #[prost(message, repeated, tag = "2")]
+    pub tx_signatures: ::prost::alloc::vec::Vec<GenericSignature>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GenericSignature {
+    /// The signature as serialized on chain i.e. the scheme flag followed by the scheme specific bytes
+    #[prost(bytes = "vec", tag = "5")]
+    pub bytes: ::prost::alloc::vec::Vec<u8>,
+    #[prost(oneof = "generic_signature::Signature", tags = "1, 2, 3, 4")]
+    pub signature: ::core::option::Option<generic_signature::Signature>,
+}
+/// Nested message and enum types in `GenericSignature`.
+pub mod generic_signature {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Signature {
+        #[prost(message, tag = "1")]
+        SimpleSignature(super::SimpleSignature),
+        #[prost(message, tag = "2")]
+        Multisig(super::MultiSig),
+        /// Deprecated multisig format. Only the raw bytes are provided
+        #[prost(message, tag = "3")]
+        MultisigLegacy(()),
+        #[prost(message, tag = "4")]
+        ZkLoginAuthenticator(super::ZkLoginAuthenticator),
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SignatureScheme {
+    #[prost(oneof = "signature_scheme::SignatureScheme", tags = "1, 2, 3, 4, 5, 6")]
+    pub signature_scheme: ::core::option::Option<signature_scheme::SignatureScheme>,
+}
+/// Nested message and enum types in `SignatureScheme`.
+pub mod signature_scheme {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum SignatureScheme {
+        #[prost(message, tag = "1")]
+        Ed25519(()),
+        #[prost(message, tag = "2")]
+        Secp256k1(()),
+        #[prost(message, tag = "3")]
+        Secp256r1(()),
+        #[prost(message, tag = "4")]
+        Bls12381(()),
+        #[prost(message, tag = "5")]
+        Multisig(()),
+        #[prost(message, tag = "6")]
+        ZkLoginAuthenticator(()),
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SimpleSignature {
+    #[prost(message, optional, tag = "1")]
+    pub scheme: ::core::option::Option<SignatureScheme>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub signature: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "3")]
+    pub public_key: ::prost::alloc::vec::Vec<u8>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MultiSig {
+    /// Signatures of the members that signed, ordered by member index
+    #[prost(message, repeated, tag = "1")]
+    pub sigs: ::prost::alloc::vec::Vec<CompressedSignature>,
+    /// Bit i is set if the member at index i signed
+    #[prost(uint32, tag = "2")]
+    pub bitmap: u32,
+    #[prost(message, optional, tag = "3")]
+    pub multisig_pk: ::core::option::Option<MultiSigPublicKey>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CompressedSignature {
+    #[prost(message, optional, tag = "1")]
+    pub scheme: ::core::option::Option<SignatureScheme>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub signature: ::prost::alloc::vec::Vec<u8>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MultiSigPublicKey {
+    #[prost(message, repeated, tag = "1")]
+    pub members: ::prost::alloc::vec::Vec<MultiSigMember>,
+    #[prost(uint32, tag = "2")]
+    pub threshold: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MultiSigMember {
+    #[prost(message, optional, tag = "1")]
+    pub scheme: ::core::option::Option<SignatureScheme>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub public_key: ::prost::alloc::vec::Vec<u8>,
+    #[prost(uint32, tag = "3")]
+    pub weight: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ZkLoginAuthenticator {
+    /// The OpenID provider that issued the JWT
+    #[prost(string, tag = "1")]
+    pub iss: ::prost::alloc::string::String,
+    /// Decimal string derived from the user identifier and the salt. Together with iss it determines the sender address
+    #[prost(string, tag = "2")]
+    pub address_seed: ::prost::alloc::string::String,
+    /// The last epoch in which the ephemeral key is valid
+    #[prost(uint64, tag = "3")]
+    pub max_epoch: u64,
+    /// Signature of the ephemeral key
+    #[prost(message, optional, tag = "4")]
+    pub user_signature: ::core::option::Option<SimpleSignature>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -582,6 +704,9 @@ pub struct IndexedEvent {
     pub bcs: ::prost::alloc::vec::Vec<u8>,
     #[prost(uint64, tag = "10")]
     pub timestamp_ms: u64,
+    /// Only set when running with --decode-events
+    #[prost(message, optional, tag = "11")]
+    pub decoded: ::core::option::Option<DecodedValue>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -632,6 +757,61 @@ pub struct StoredDisplay {
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Epoch {
+    /// The epoch that just ended. Not set for the genesis checkpoint
+    #[prost(message, optional, tag = "1")]
+    pub last_epoch: ::core::option::Option<EpochInfo>,
+    #[prost(message, optional, tag = "2")]
+    pub new_epoch: ::core::option::Option<EpochInfo>,
+    /// Total number of transactions on the network at the end of the last epoch. The number of transactions in an
+    /// epoch is the difference between two consecutive Epoch records.
+    #[prost(uint64, tag = "3")]
+    pub network_total_transactions: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct EpochInfo {
+    #[prost(uint64, tag = "1")]
+    pub epoch: u64,
+    #[prost(uint64, tag = "2")]
+    pub first_checkpoint_id: u64,
+    #[prost(uint64, tag = "3")]
+    pub epoch_start_timestamp: u64,
+    #[prost(uint64, tag = "4")]
+    pub reference_gas_price: u64,
+    #[prost(uint64, tag = "5")]
+    pub protocol_version: u64,
+    #[prost(uint64, tag = "6")]
+    pub total_stake: u64,
+    #[prost(uint64, tag = "7")]
+    pub storage_fund_balance: u64,
+    /// BCS encoded SuiSystemStateSummary at the start of the epoch. It includes the validator set.
+    #[prost(bytes = "vec", tag = "8")]
+    pub system_state: ::prost::alloc::vec::Vec<u8>,
+    /// The fields below are only set once the epoch has ended
+    #[prost(uint64, optional, tag = "9")]
+    pub last_checkpoint_id: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "10")]
+    pub epoch_end_timestamp: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "11")]
+    pub storage_fund_reinvestment: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "12")]
+    pub storage_charge: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "13")]
+    pub storage_rebate: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "14")]
+    pub stake_subsidy_amount: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "15")]
+    pub total_gas_fees: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "16")]
+    pub total_stake_rewards_distributed: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "17")]
+    pub leftover_storage_fund_inflow: ::core::option::Option<u64>,
+    #[prost(message, repeated, tag = "18")]
+    pub epoch_commitments: ::prost::alloc::vec::Vec<CheckpointCommitment>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct TransactionObjectChange {
     #[prost(message, repeated, tag = "1")]
     pub changed_objects: ::prost::alloc::vec::Vec<IndexedObject>,
@@ -661,6 +841,9 @@ pub struct IndexedObject {
     pub coin_type: ::core::option::Option<::prost::alloc::string::String>,
     #[prost(uint64, optional, tag = "10")]
     pub coin_balance: ::core::option::Option<u64>,
+    /// Only set for objects that are dynamic fields e.g. the entries of tables, bags and kiosks
+    #[prost(message, optional, tag = "11")]
+    pub df_info: ::core::option::Option<DynamicFieldInfo>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -734,6 +917,9 @@ pub struct MoveObject {
     pub version: u64,
     #[prost(bytes = "vec", tag = "4")]
     pub contents: ::prost::alloc::vec::Vec<u8>,
+    /// Only set when running with --decode-objects and the type passes the allow/deny lists
+    #[prost(message, optional, tag = "5")]
+    pub decoded: ::core::option::Option<DecodedValue>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -867,8 +1053,8 @@ pub mod value {
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ValueMap {
-    #[prost(map = "string, message", tag = "6")]
-    pub map: ::std::collections::HashMap<::prost::alloc::string::String, Value>,
+    #[prost(btree_map = "string, message", tag = "6")]
+    pub map: ::prost::alloc::collections::BTreeMap<::prost::alloc::string::String, Value>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -876,6 +1062,26 @@ pub struct ListOfValues {
     #[prost(message, repeated, tag = "1")]
     pub list: ::prost::alloc::vec::Vec<Value>,
 }
+/// BCS encoded Move value decoded with the layout of its type. Structs become objects keyed by field name and
+/// u64, u128 and u256 are strings so that they don't lose precision
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DecodedValue {
+    #[prost(oneof = "decoded_value::Decoded", tags = "1, 2")]
+    pub decoded: ::core::option::Option<decoded_value::Decoded>,
+}
+/// Nested message and enum types in `DecodedValue`.
+pub mod decoded_value {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Decoded {
+        #[prost(message, tag = "1")]
+        Value(super::Value),
+        /// Why the value could not be decoded e.g. the package that defines its type has not been seen yet
+        #[prost(string, tag = "2")]
+        Error(::prost::alloc::string::String),
+    }
+}
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct EndOfEpochData {
@@ -1089,6 +1295,14 @@ pub struct ProgrammableTransaction {
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CallArg {
+    /// Move type of a pure value inferred from the signature of the function it is passed to or from the command
+    /// that uses it e.g. SplitCoins amounts are u64 and TransferObjects recipients are addresses. Not set if the
+    /// type could not be inferred
+    #[prost(message, optional, tag = "3")]
+    pub pure_type: ::core::option::Option<TypeTag>,
+    /// Pure value decoded with the inferred type
+    #[prost(message, optional, tag = "4")]
+    pub pure_decoded: ::core::option::Option<DecodedValue>,
     #[prost(oneof = "call_arg::CallArg", tags = "1, 2")]
     pub call_arg: ::core::option::Option<call_arg::CallArg>,
 }
@@ -1,18 +1,68 @@
 use std::{
-  sync::{mpsc::sync_channel, Arc, Mutex}, panic, process, mem
+  path::PathBuf, sync::{Arc, Mutex, OnceLock}, panic, process, mem, time::Duration,
 };
+use backoff::{future::retry, ExponentialBackoff};
 use ctrlc;
-use log::info;
+use eyre::{eyre, Result};
+use log::{error, info, warn};
 use tokio::{
-  spawn, sync::oneshot::{channel, Sender}, task::JoinHandle,
+  select, spawn,
+  sync::{mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender}, oneshot::{channel, Sender}, watch},
+  task::JoinHandle,
+  time::{timeout_at, Instant},
 };
-use crate::{args::Args, logger::Logger, runtime::FirehoseStreamer, sui::sui_node::SuiNode};
+use crate::{
+  args::{Args, RpcApi}, batch_size::BatchSize, checkpoint_dir::CheckpointDir,
+  checkpoint_source::CheckpointSource, cursor::{resolve_starting_checkpoint_seq, CursorFile},
+  endpoints::{EndpointPool, RequestGate, RequestLimits}, json_rpc_source::JsonRpcSource, logger::Logger,
+  runtime::{FirehoseStreamer, StreamerConfig},
+  sui::{module_resolver::SuiModuleResolver, sui_node::SuiNode},
+  supervisor::{is_supervised, RestartPolicy}, type_filter::TypeFilter,
+};
+
+/// How long we keep probing the rpc endpoints at startup e.g. while a local sui-node that was just spawned starts up
+const RPC_API_PROBE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The shutdown channel of every run in progress. Panic and signal hooks are process-wide so they are installed
+/// once and notify whichever runs are registered at the time
+static RUNS: OnceLock<Mutex<Vec<UnboundedSender<Shutdown>>>> = OnceLock::new();
 
+fn notify_runs(shutdown: Shutdown) {
+  // Called from the panic hook where panicking again would abort the process
+  let Some(Ok(runs)) = RUNS.get().map(|runs| runs.lock()) else {
+    return
+  };
+
+  for tx in runs.iter() {
+    let _ = tx.send(shutdown);
+  }
+}
+
+/// Why the process is shutting down
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shutdown {
+  /// The streamer stopped on its own after emitting everything it had committed to, either because it reached
+  /// the stop checkpoint or because it was asked to drain
+  Finished,
+  /// The process received SIGINT or SIGTERM
+  Interrupted,
+  /// A task failed or panicked, or in-flight checkpoints could not be drained in time
+  Aborted,
+}
+
+impl Shutdown {
+  fn exit_code(&self) -> i32 {
+    match self {
+      Shutdown::Finished | Shutdown::Interrupted => 0,
+      Shutdown::Aborted => 1,
+    }
+  }
+}
 
 #[derive(Default)]
 struct ProcessManagerInner {
   args: Args,
-  tasks: Vec<Sender<()>>,
+  tasks: Vec<(Sender<()>, JoinHandle<()>)>,
 }
 
 pub struct ProcessManager(Arc<Mutex<ProcessManagerInner>>);
@@ -24,64 +74,150 @@ impl ProcessManager {
     ProcessManager(Arc::new(Mutex::new(pm)))
   }
 
-  fn register_hooks(&self)  {
-    let (tx, rx) = sync_channel(2);
-    let tx_2 = tx.clone();
-    let orig_hook = panic::take_hook();
+  fn register_hooks(&self, tx: UnboundedSender<Shutdown>)  {
+    let runs = RUNS.get_or_init(|| {
+      let orig_hook = panic::take_hook();
+
+      // this hook will be called if any of the threads panics
+      panic::set_hook(Box::new(move |panic_info| {
+        eprintln!("Thread Panicked {:?}", panic_info);
 
-    // this hook will be called if any of the threads panics
-    panic::set_hook(Box::new(move |panic_info| {
-      println!("Thread Panicked {:?}", panic_info);
+        // Supervised tasks are restarted by whoever spawned them so there is no need to bring everything down
+        if !is_supervised() {
+          notify_runs(Shutdown::Aborted);
+        }
 
-      tx_2.send(()).expect("send msg");
-      orig_hook(panic_info);
-    }));
+        orig_hook(panic_info);
+      }));
 
-    let tx_3 = tx.clone();
-    ctrlc::set_handler(move || {
-      tx_3.send(()).expect("send msg");
-    }).unwrap();
+      // With the termination feature enabled this fires on SIGTERM as well
+      if let Err(err) = ctrlc::set_handler(|| notify_runs(Shutdown::Interrupted)) {
+        warn!("Could not install the SIGINT/SIGTERM handler: {}", err);
+      }
+
+      Mutex::default()
+    });
 
-    let _ = rx.recv();
-    self.kill_all();
+    runs.lock().unwrap().push(tx);
   }
 
+  fn unregister_hooks(&self, tx: &UnboundedSender<Shutdown>) {
+    if let Some(runs) = RUNS.get() {
+      runs.lock().unwrap().retain(|run| !run.same_channel(tx));
+    }
+  }
+
+  /// Runs until the streamer stops and exits the process with a status that reflects why it stopped
   pub async fn start<L>(&self, logger: L)
   where
     L: Logger + Sync + Send + 'static
   {
-    let mut tasks = vec![];
+    let shutdown = self.run(logger).await;
+    process::exit(shutdown.exit_code());
+  }
 
-    let pm = Arc::clone(&self.0);
-    let rpc_client_url = pm.lock().unwrap().args.rpc_client_url.clone();
+  /// Same as `start` except that once every task has stopped it returns why instead of exiting the process
+  pub async fn run<L>(&self, logger: L) -> Shutdown
+  where
+    L: Logger + Sync + Send + 'static
+  {
+    let (tx, rx) = unbounded_channel();
+    self.register_hooks(tx.clone());
+    let shutdown = self.run_until_shutdown(logger, tx.clone(), rx).await;
+    self.unregister_hooks(&tx);
 
-    // If no rpc url provided the we need to start a local sui-node
-    let rpc_client_url = if let Some(rpc_client_url) = rpc_client_url {
-      rpc_client_url.clone()
-    } else {
-      tasks.push(self.spawn_sui_node());
-      "http://127.0.0.1:9000".to_string()
+    shutdown
+  }
+
+  async fn run_until_shutdown<L>(
+    &self,
+    logger: L,
+    tx: UnboundedSender<Shutdown>,
+    mut rx: UnboundedReceiver<Shutdown>,
+  ) -> Shutdown
+  where
+    L: Logger + Sync + Send + 'static
+  {
+    let pm = Arc::clone(&self.0);
+    let (mut rpc_client_urls, has_other_source) = {
+      let pm = pm.lock().unwrap();
+      (pm.args.rpc_client_urls.clone(), pm.args.source.is_some() || pm.args.replay.is_some())
     };
 
-    tasks.push(self.spawn_firehose_streamer::<L>(rpc_client_url, logger));
-    self.register_hooks();
+    // If no rpc url nor any other source is provided the we need to start a local sui-node
+    if rpc_client_urls.is_empty() && !has_other_source {
+      self.spawn_sui_node();
+      rpc_client_urls.push("http://127.0.0.1:9000".to_string());
+    }
+
+    let (stop_streamer, stop_streamer_rx) = watch::channel(false);
+    self.spawn_firehose_streamer::<L>(rpc_client_urls, logger, stop_streamer_rx, tx);
+
+    let shutdown = rx.recv().await.unwrap_or(Shutdown::Aborted);
+    if shutdown != Shutdown::Interrupted {
+      info!("Killing all processes");
+      self.stop_tasks().await;
+      return shutdown
+    }
+
+    let shutdown_timeout = Duration::from_secs(pm.lock().unwrap().args.shutdown_timeout_secs);
+    let deadline = Instant::now() + shutdown_timeout;
+    let shutdown = self.drain_streamer(&mut rx, stop_streamer, deadline).await;
+
+    if timeout_at(deadline, self.stop_tasks()).await.is_err() {
+      error!("Processes did not stop within {:?}", shutdown_timeout);
+      return Shutdown::Aborted
+    }
+
+    shutdown
+  }
+
+  /// The streamer stops its fetchers first and then emits every checkpoint that is already in flight. The cursor
+  /// is persisted after each of them so a restart picks up right after the last one.
+  async fn drain_streamer(
+    &self,
+    rx: &mut UnboundedReceiver<Shutdown>,
+    stop_streamer: watch::Sender<bool>,
+    deadline: Instant,
+  ) -> Shutdown {
+    info!("Shutting down, draining in-flight checkpoints");
+    stop_streamer.send_replace(true);
+
+    match timeout_at(deadline, rx.recv()).await {
+      Ok(Some(Shutdown::Finished)) => Shutdown::Interrupted,
+      Ok(Some(Shutdown::Interrupted)) => {
+        warn!("Received a second termination signal, exiting without draining");
+        Shutdown::Aborted
+      },
+      Ok(Some(Shutdown::Aborted)) | Ok(None) => Shutdown::Aborted,
+      Err(_) => {
+        error!("In-flight checkpoints were not drained in time");
+        Shutdown::Aborted
+      },
+    }
   }
 
-  fn spawn_sui_node (&self) -> JoinHandle<()> {
+  fn spawn_sui_node (&self) {
     let (tx, rx) = channel();
     let pm = Arc::clone(&self.0);
     let mut pm = pm.lock().unwrap();
     let sui_config = pm.args.sui_node_config.clone();
 
-    pm.tasks.push(tx);
-
-    spawn(async move {
+    let handle = spawn(async move {
       let sui_node = SuiNode::new(sui_config);
       sui_node.start(rx).await;
-    })
+    });
+
+    pm.tasks.push((tx, handle));
   }
 
-  fn spawn_firehose_streamer<L>(&self, rpc_client_url: String, logger: L) -> JoinHandle<()>
+  fn spawn_firehose_streamer<L>(
+    &self,
+    rpc_client_urls: Vec<String>,
+    logger: L,
+    stop_streamer: watch::Receiver<bool>,
+    shutdown_tx: UnboundedSender<Shutdown>,
+  )
   where
     L: Logger + Sync + Send + 'static
   {
@@ -89,26 +225,162 @@ impl ProcessManager {
     let pm = pm.lock().unwrap();
     let chain_id = pm.args.chain_id.clone();
     let starting_checkpoint_seq = pm.args.starting_checkpoint_seq.clone();
+    let stop_checkpoint = pm.args.stop_checkpoint;
+    let output_format = pm.args.output_format;
+    let cursor_file = pm.args.cursor_file.clone().map(CursorFile::new);
+    let gap_timeout = Duration::from_secs(pm.args.gap_timeout_secs);
+    let reorder_buffer_size = pm.args.reorder_buffer_size;
+    let backfill_workers = pm.args.backfill_workers;
+    let backfill_window = pm.args.backfill_window;
+    let restart_policy = RestartPolicy::new(pm.args.max_consecutive_failures);
+    let request_limits = RequestLimits {
+      max_in_flight_requests: pm.args.max_in_flight_requests,
+      max_requests_per_sec: pm.args.max_requests_per_sec,
+    };
+    let download_queue_size = pm.args.download_queue_size;
+    let checkpoint_queue_size = pm.args.checkpoint_queue_size;
+    let batch_size = if pm.args.adaptive_batch_size {
+      BatchSize::adaptive(pm.args.batch_size)
+    } else {
+      BatchSize::fixed(pm.args.batch_size)
+    };
+    let source = pm.args.source.clone();
+    let rpc_api = pm.args.rpc_api;
+    let record_dir = pm.args.record.clone().map(PathBuf::from);
+    let replay_dir = pm.args.replay.clone().map(|replay| CheckpointDir::new(PathBuf::from(replay)));
+    let module_cache_dir = pm.args.module_cache_dir.clone().map(PathBuf::from);
+    let decode_events = pm.args.decode_events;
+    let decode_pure_inputs = pm.args.decode_pure_inputs;
+    let decode_objects = pm.args.decode_objects;
+    let decode_objects_allow = pm.args.decode_objects_allow.clone();
+    let decode_objects_deny = pm.args.decode_objects_deny.clone();
 
     spawn(async move {
-      let mut fireshose_streamer = FirehoseStreamer::<L>::new(chain_id, rpc_client_url, starting_checkpoint_seq, logger);
-      if let Err(e) = fireshose_streamer.start().await {
-        panic!("{}", e);
-      }
-    })
-  }
+      let result = async {
+        let starting_checkpoint_seq = resolve_starting_checkpoint_seq(
+          cursor_file.as_ref(), &chain_id, starting_checkpoint_seq,
+        )?;
+        let (checkpoint_dir, stop_checkpoint) = match replay_dir {
+          Some(replay_dir) => {
+            let last_recorded = replay_dir.last_consecutive_checkpoint(starting_checkpoint_seq)
+            .ok_or_else(|| eyre!("checkpoint {} has not been recorded", starting_checkpoint_seq))?;
+            let stop_checkpoint = stop_checkpoint
+            .map_or(last_recorded, |stop_checkpoint| stop_checkpoint.min(last_recorded));
 
-  pub fn kill_all(&self) {
-    info!("Killing all processes and exiting");
+            (Some(replay_dir), Some(stop_checkpoint))
+          },
+          None => (source.as_deref().map(CheckpointDir::from_source).transpose()?, stop_checkpoint),
+        };
+        let source: Arc<dyn CheckpointSource> = match checkpoint_dir {
+          Some(checkpoint_dir) => Arc::new(checkpoint_dir),
+          None => {
+            let mut stop_streamer = stop_streamer.clone();
 
-    let pm = Arc::clone(&self.0);
-    let mut pm = pm.lock().unwrap();
-    let tasks = mem::take(&mut pm.tasks);
+            select! {
+              source = connect_rpc_source(&rpc_client_urls, request_limits, rpc_api) => source?,
+              // Nothing has been fetched yet so there is nothing to drain
+              _ = stop_streamer.wait_for(|stop| *stop) => {
+                info!("Interrupted while probing the rpc endpoints");
+                return Ok(())
+              },
+            }
+          },
+        };
+        let record_dir = record_dir.map(CheckpointDir::create).transpose()?;
+        let module_resolver = SuiModuleResolver::new(module_cache_dir)?;
+        module_resolver.bootstrap_system_packages()?;
+        let decode_objects = decode_objects
+        .then(|| TypeFilter::new(&decode_objects_allow, &decode_objects_deny))
+        .transpose()?;
+
+        let config = StreamerConfig {
+          chain_id,
+          source,
+          record_dir,
+          download_queue_size,
+          checkpoint_queue_size,
+          batch_size,
+          starting_checkpoint_seq,
+          stop_checkpoint,
+          output_format,
+          cursor_file,
+          gap_timeout,
+          reorder_buffer_size,
+          backfill_workers,
+          backfill_window,
+          shutdown: stop_streamer,
+          restart_policy,
+          module_resolver,
+          decode_events,
+          decode_pure_inputs,
+          decode_objects,
+        };
+        let mut fireshose_streamer = FirehoseStreamer::<L>::new(config, logger);
+
+        fireshose_streamer.start().await
+      }.await;
+
+      // A failing sink (e.g. the reader closed the pipe) is not a bug so rather than panicking we report where
+      // the stream stopped and let the hooks shut everything down
+      let shutdown = match result {
+        Ok(()) => Shutdown::Finished,
+        Err(e) => {
+          error!("Firehose streamer stopped: {:?}", e);
+          Shutdown::Aborted
+        },
+      };
+
+      let _ = shutdown_tx.send(shutdown);
+    });
+  }
+
+  async fn stop_tasks(&self) {
+    let tasks = {
+      let mut pm = self.0.lock().unwrap();
+      mem::take(&mut pm.tasks)
+    };
 
-    for task in tasks {
-      task.send(()).expect("send task termination messages");
+    for (tx, handle) in tasks {
+      // The task might have already exited on its own in which case nobody is listening anymore
+      let _ = tx.send(());
+      let _ = handle.await;
     }
+  }
+}
 
-    process::exit(1);
+/// Picks the API the checkpoints are fetched through. In auto mode the endpoints are probed until either API
+/// answers, favoring the REST API which takes a single request per checkpoint.
+async fn connect_rpc_source(
+  urls: &[String],
+  limits: RequestLimits,
+  rpc_api: RpcApi,
+) -> Result<Arc<dyn CheckpointSource>> {
+  match rpc_api {
+    RpcApi::Rest => return Ok(Arc::new(EndpointPool::new(urls, limits)?)),
+    RpcApi::JsonRpc => return Ok(Arc::new(JsonRpcSource::connect(urls, RequestGate::new(limits)?).await?)),
+    RpcApi::Auto => {},
   }
+
+  let endpoints = EndpointPool::new(urls, limits)?;
+  let backoff = ExponentialBackoff {
+    max_elapsed_time: Some(RPC_API_PROBE_TIMEOUT),
+    ..Default::default()
+  };
+
+  retry(backoff, || async {
+    let rest_err = match endpoints.latest_checkpoint().await {
+      Ok(_) => {
+        info!("Fetching checkpoints through the REST API");
+        return Ok(Arc::new(endpoints.clone()) as Arc<dyn CheckpointSource>)
+      },
+      Err(err) => err,
+    };
+
+    // Shares the limits with the REST probes since both go to the same endpoints
+    let json_rpc_source = JsonRpcSource::connect(urls, endpoints.request_gate().clone()).await
+    .map_err(|err| eyre!("neither the REST API ({}) nor the JSON-RPC API ({}) is available", rest_err, err))?;
+    warn!("The REST API is not available ({}), fetching checkpoints through JSON-RPC", rest_err);
+
+    Ok(Arc::new(json_rpc_source) as Arc<dyn CheckpointSource>)
+  }).await
 }
@@ -0,0 +1,83 @@
+use std::{sync::{Arc, Mutex}, time::Duration};
+use tokio::time::{sleep, Instant};
+
+struct Bucket {
+  tokens: f64,
+  capacity: f64,
+  refill_per_sec: f64,
+  refilled_at: Instant,
+}
+
+impl Bucket {
+  /// Takes a token if there is one, otherwise returns how long it takes for the next one to become available
+  fn try_take(&mut self, now: Instant) -> Result<(), Duration> {
+    let elapsed = now.saturating_duration_since(self.refilled_at).as_secs_f64();
+    self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+    self.refilled_at = now;
+
+    if self.tokens >= 1.0 {
+      self.tokens -= 1.0;
+      return Ok(())
+    }
+
+    Err(Duration::from_secs_f64((1.0 - self.tokens) / self.refill_per_sec))
+  }
+}
+
+/// Token bucket shared by everything that sends requests to the same provider. It allows bursts of up to one
+/// second worth of requests and the given rate on average.
+#[derive(Clone)]
+pub struct RateLimiter {
+  bucket: Arc<Mutex<Bucket>>,
+}
+
+impl RateLimiter {
+  pub fn new(requests_per_sec: u32) -> Self {
+    let rate = requests_per_sec.max(1) as f64;
+    let bucket = Bucket {
+      tokens: rate,
+      capacity: rate,
+      refill_per_sec: rate,
+      refilled_at: Instant::now(),
+    };
+
+    Self {bucket: Arc::new(Mutex::new(bucket))}
+  }
+
+  /// Waits until a request can be sent
+  pub async fn acquire(&self) {
+    loop {
+      let wait = match self.bucket.lock().unwrap().try_take(Instant::now()) {
+        Ok(()) => return,
+        Err(wait) => wait,
+      };
+
+      sleep(wait).await;
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+  use tokio::time::Instant;
+  use super::RateLimiter;
+
+  // The clock only moves when every task is waiting on a timer so the elapsed time is exactly what was slept
+  #[tokio::test(start_paused = true)]
+  async fn limits_the_rate_after_the_initial_burst() {
+    let rate_limiter = RateLimiter::new(50);
+    let started_at = Instant::now();
+
+    for _ in 0..50 {
+      rate_limiter.acquire().await;
+    }
+    assert_eq!(started_at.elapsed(), Duration::ZERO);
+
+    // The next 10 need 200ms worth of tokens
+    for _ in 0..10 {
+      rate_limiter.acquire().await;
+    }
+    assert_eq!(started_at.elapsed(), Duration::from_millis(200));
+  }
+}
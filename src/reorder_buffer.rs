@@ -0,0 +1,150 @@
+use std::{collections::BTreeMap, ops::RangeInclusive};
+
+/// Holds items that arrived ahead of their turn and releases them strictly in sequence order
+pub struct ReorderBuffer<T> {
+  next_seq: u64,
+  pending: BTreeMap<u64, T>,
+  capacity: usize,
+  /// The highest item turned away because the buffer was full. It is still missing once the buffer drains
+  last_skipped: Option<u64>,
+}
+
+impl<T> ReorderBuffer<T> {
+  pub fn new(next_seq: u64, capacity: usize) -> Self {
+    Self {
+      next_seq,
+      pending: BTreeMap::new(),
+      capacity,
+      last_skipped: None,
+    }
+  }
+
+  /// The sequence number of the next item that will be released
+  pub fn next_seq(&self) -> u64 {
+    self.next_seq
+  }
+
+  /// Returns false if the item has already been released or is already buffered. Such duplicates are dropped.
+  pub fn push(&mut self, seq: u64, item: T) -> bool {
+    if seq < self.next_seq || self.pending.contains_key(&seq) {
+      return false
+    }
+
+    self.pending.insert(seq, item);
+    true
+  }
+
+  /// Releases the next item if it has arrived
+  pub fn pop(&mut self) -> Option<T> {
+    let item = self.pending.remove(&self.next_seq)?;
+    self.next_seq += 1;
+
+    Some(item)
+  }
+
+  /// Records an item that was not buffered because the buffer is full so that it is reported as missing again
+  pub fn skip(&mut self, seq: u64) {
+    self.last_skipped = self.last_skipped.max(Some(seq));
+  }
+
+  /// The range of sequence numbers that must arrive before any of the buffered items can be released, or that were
+  /// skipped once the buffer is empty
+  pub fn missing_range(&self) -> Option<RangeInclusive<u64>> {
+    let Some((first_pending, _)) = self.pending.first_key_value() else {
+      return self.last_skipped
+      .filter(|last_skipped| *last_skipped >= self.next_seq)
+      .map(|last_skipped| self.next_seq..=last_skipped)
+    };
+
+    (*first_pending > self.next_seq).then(|| self.next_seq..=first_pending - 1)
+  }
+
+  /// Whether the item is one of those holding back the buffered items
+  pub fn is_missing(&self, seq: u64) -> bool {
+    self.missing_range().map_or(false, |missing| missing.contains(&seq))
+  }
+
+  pub fn len(&self) -> usize {
+    self.pending.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.pending.is_empty()
+  }
+
+  pub fn is_full(&self) -> bool {
+    self.pending.len() >= self.capacity
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::ReorderBuffer;
+
+  #[test]
+  fn releases_items_in_order() {
+    let mut buffer = ReorderBuffer::new(1, 10);
+
+    assert!(buffer.push(3, "c"));
+    assert!(buffer.push(2, "b"));
+    assert_eq!(buffer.pop(), None);
+    assert_eq!(buffer.missing_range(), Some(1..=1));
+
+    assert!(buffer.push(1, "a"));
+    assert_eq!(buffer.missing_range(), None);
+    assert_eq!(buffer.pop(), Some("a"));
+    assert_eq!(buffer.pop(), Some("b"));
+    assert_eq!(buffer.pop(), Some("c"));
+    assert_eq!(buffer.pop(), None);
+    assert_eq!(buffer.next_seq(), 4);
+  }
+
+  #[test]
+  fn drops_duplicates() {
+    let mut buffer = ReorderBuffer::new(1, 10);
+
+    assert!(buffer.push(1, "a"));
+    assert!(!buffer.push(1, "a"));
+    assert_eq!(buffer.pop(), Some("a"));
+    assert!(!buffer.push(1, "a"));
+    assert!(buffer.is_empty());
+  }
+
+  #[test]
+  fn reports_missing_range() {
+    let mut buffer = ReorderBuffer::new(5, 2);
+
+    assert!(buffer.push(9, "e"));
+    assert_eq!(buffer.missing_range(), Some(5..=8));
+    assert!(!buffer.is_full());
+
+    assert!(buffer.push(7, "c"));
+    assert_eq!(buffer.missing_range(), Some(5..=6));
+    assert!(buffer.is_full());
+    assert!(buffer.is_missing(5));
+    assert!(buffer.is_missing(6));
+    assert!(!buffer.is_missing(8));
+    assert!(!buffer.is_missing(10));
+  }
+
+  #[test]
+  fn reports_skipped_items_as_missing() {
+    let mut buffer = ReorderBuffer::new(1, 1);
+
+    assert!(buffer.push(2, "b"));
+    buffer.skip(3);
+    buffer.skip(4);
+    assert_eq!(buffer.missing_range(), Some(1..=1));
+
+    assert!(buffer.push(1, "a"));
+    assert_eq!(buffer.pop(), Some("a"));
+    assert_eq!(buffer.pop(), Some("b"));
+    assert_eq!(buffer.missing_range(), Some(3..=4));
+
+    assert!(buffer.push(3, "c"));
+    assert!(buffer.push(4, "d"));
+    assert_eq!(buffer.pop(), Some("c"));
+    assert_eq!(buffer.pop(), Some("d"));
+    assert_eq!(buffer.missing_range(), None);
+  }
+}
@@ -1,39 +1,167 @@
-use eyre::Result;
-use futures::StreamExt;
+use std::{ops::RangeInclusive, sync::Arc, time::Duration};
+use eyre::{bail, ensure, eyre, Report, Result, WrapErr};
+use futures::{future::pending, FutureExt, Stream, StreamExt};
 use mysten_metrics::{
   init_metrics, get_metrics, metered_channel::{channel, Sender, Receiver, ReceiverStream},
 };
 use sui_indexer::{
-  framework::{fetcher::{CheckpointFetcher, CheckpointDownloadData}, Handler},
+  framework::{fetcher::CheckpointDownloadData, Handler},
   handlers::{
     checkpoint_handler::CheckpointHandler, CheckpointDataToCommit,
   },
   metrics::IndexerMetrics,
 };
-use sui_rest_api::Client;
-use backoff::{ExponentialBackoff, future::retry};
+use backoff::ExponentialBackoff;
 use prost::Message;
-use log::{debug, error};
+use log::{debug, error, info, warn};
 use prometheus::Registry;
-use tokio::spawn;
+use sui_types::digests::CheckpointDigest;
+use tokio::{
+  select, sync::watch, task::{spawn_blocking, JoinSet}, time::{error::Elapsed, sleep_until, timeout_at, Instant},
+};
 use crate::{
+  args::OutputFormat,
+  batch_size::BatchSize,
+  checkpoint_dir::CheckpointDir,
+  checkpoint_source::CheckpointSource,
+  cursor::{Cursor, CursorFile},
+  fetcher::{download_checkpoint, split_range, RangeFetcher, TailFetcher, Window},
+  reorder_buffer::ReorderBuffer,
+  sui::{module_resolver::SuiModuleResolver, move_decoder::MoveDecoder},
+  supervisor::{spawn_supervised, RestartPolicy},
+  type_filter::TypeFilter,
   convert::{
-    checkpoint::convert_checkpoint, display_update::convert_display_update, sui_event::convert_indexed_event,
-    tx::convert_transaction, tx_object_change::convert_tx_object_changes,
+    checkpoint::convert_checkpoint,
+    checkpoint_data::{convert_checkpoint_data, decode_checkpoint_data_events, decode_checkpoint_data_pure_inputs},
+    display_update::convert_display_update, epoch::convert_epoch, package::convert_indexed_packages,
+    sui_event::{convert_indexed_event, decode_indexed_event},
+    tx::{convert_transaction, decode_pure_inputs, decode_transaction_events}, tx_index::convert_tx_index,
+    tx_object_change::{convert_tx_object_changes, decode_tx_object_changes},
   }, logger::Logger, pb::sui::checkpoint as pb
 };
 
-const DOWNLOAD_QUEUE_SIZE: usize = 1000;
-const CHECKPOINT_QUEUE_SIZE: usize = 1000;
-const CHECKPOINT_PROCESSING_BATCH_SIZE: usize = 25;
+/// Version of the Firehose reader protocol that understands the single line FIRE BLOCK format
+const FIRE_PROTOCOL_VERSION: &str = "3.0";
+const BLOCK_TYPE_URL: &str = "sui.checkpoint.v1.CheckpointData";
+
+pub struct StreamerConfig {
+  pub chain_id: String,
+  /// Where the fetchers get the checkpoints from e.g. the rpc endpoints or a directory of checkpoint files
+  pub source: Arc<dyn CheckpointSource>,
+  /// Every checkpoint is written to this directory before it is processed so that the run can be replayed
+  pub record_dir: Option<CheckpointDir>,
+  /// Capacity of the queue between each fetcher and its checkpoint handler
+  pub download_queue_size: usize,
+  /// Capacity of the queue between the checkpoint handlers and the commit loop
+  pub checkpoint_queue_size: usize,
+  /// How many checkpoints a handler processes at once
+  pub batch_size: BatchSize,
+  pub starting_checkpoint_seq: u64,
+  /// Last checkpoint to emit. The stream runs forever if not set
+  pub stop_checkpoint: Option<u64>,
+  pub output_format: OutputFormat,
+  pub cursor_file: Option<CursorFile>,
+  /// How long we wait for a missing checkpoint before giving up
+  pub gap_timeout: Duration,
+  /// Max number of checkpoints that can be held back while waiting for a missing one
+  pub reorder_buffer_size: usize,
+  /// Number of parallel fetcher and handler pipelines. Anything above 1 requires a stop checkpoint
+  pub backfill_workers: usize,
+  /// Max number of checkpoints that parallel workers can fetch ahead of the next checkpoint to be emitted
+  pub backfill_window: usize,
+  /// Flipping this to true stops the fetchers and lets the streamer drain whatever is already in flight
+  pub shutdown: watch::Receiver<bool>,
+  /// How often the fetcher and handler tasks are restarted before the streamer gives up
+  pub restart_policy: RestartPolicy,
+  /// Fed with every package that is published or upgraded before the checkpoint that contains it is emitted
+  pub module_resolver: SuiModuleResolver,
+  /// Attach the decoded contents to every event
+  pub decode_events: bool,
+  /// Attach the inferred type and the decoded value to the pure inputs of programmable transactions
+  pub decode_pure_inputs: bool,
+  /// Attach the decoded contents to the changed Move objects whose type passes the filter. Disabled if not set
+  pub decode_objects: Option<TypeFilter>,
+}
+
+/// A run of missing checkpoints that is currently blocking the stream
+struct Gap {
+  start: u64,
+  deadline: Instant,
+}
+
+/// The tasks that download checkpoints and run them through the checkpoint handlers on their way to the commit
+/// loop. If any of them fails they are all restarted from the last committed checkpoint.
+struct Pipeline {
+  /// Keeps the commit loop's stream open across restarts. Dropped when draining so that the stream can end.
+  handle_checkpoint_sender: Option<Sender<CheckpointDataToCommit>>,
+  next_seq: watch::Receiver<u64>,
+  fetchers: JoinSet<Result<()>>,
+  handlers: JoinSet<Result<()>>,
+  /// Where missing checkpoints are sent to when a gap opens up. Parallel workers keep retrying their own
+  /// checkpoints so this is only set when there is a single worker.
+  refetch_sender: Option<Sender<CheckpointDownloadData>>,
+}
+
+impl Pipeline {
+  /// Resolves with the cause of the first task that fails or panics. Tasks that are done with their work or that
+  /// were aborted are not failures.
+  async fn failure(&mut self) -> eyre::Report {
+    loop {
+      let result = select! {
+        Some(result) = self.fetchers.join_next() => result,
+        Some(result) = self.handlers.join_next() => result,
+        else => return pending().await,
+      };
+
+      match result {
+        Ok(Ok(())) => continue,
+        Ok(Err(err)) => return err,
+        Err(err) if err.is_cancelled() => continue,
+        Err(err) => return eyre!("task panicked: {}", err),
+      }
+    }
+  }
+
+  /// Aborting the fetchers drops their senders. Once the handlers have processed whatever is left in their queues
+  /// they drop theirs too which eventually ends the stream the commit loop is reading from.
+  fn stop_fetchers(&mut self) {
+    self.fetchers.abort_all();
+    self.refetch_sender = None;
+    self.handle_checkpoint_sender = None;
+  }
+
+  fn abort(&mut self) {
+    self.fetchers.abort_all();
+    self.handlers.abort_all();
+    self.refetch_sender = None;
+  }
+}
 
 pub  struct FirehoseStreamer<L>
 where
   L: Logger
 {
   pub current_checkpoint_seq: u64,
-  rpc_client_url: String,
+  stop_checkpoint: Option<u64>,
+  source: Arc<dyn CheckpointSource>,
+  record_dir: Option<CheckpointDir>,
+  download_queue_size: usize,
+  checkpoint_queue_size: usize,
+  batch_size: BatchSize,
   chain_id: String,
+  output_format: OutputFormat,
+  cursor_file: Option<CursorFile>,
+  gap_timeout: Duration,
+  reorder_buffer_size: usize,
+  backfill_workers: usize,
+  backfill_window: usize,
+  shutdown: watch::Receiver<bool>,
+  restart_policy: RestartPolicy,
+  module_resolver: SuiModuleResolver,
+  move_decoder: MoveDecoder,
+  decode_events: bool,
+  decode_pure_inputs: bool,
+  decode_objects: Option<TypeFilter>,
   metrics: IndexerMetrics,
   logger: L,
 }
@@ -42,158 +170,419 @@ impl <L> FirehoseStreamer<L>
 where
   L: Logger
 {
-  pub fn new(
-    chain_id: String,
-    rpc_client_url: String,
-    starting_checkpoint_seq: u64,
-    logger: L,
-  ) -> Self {
+  pub fn new(config: StreamerConfig, logger: L) -> Self {
     let registry = Registry::default();
     init_metrics(&registry);
     let metrics = IndexerMetrics::new(&registry);
+    let move_decoder = MoveDecoder::new(config.module_resolver.clone());
 
     Self {
-      current_checkpoint_seq: starting_checkpoint_seq,
-      rpc_client_url,
-      chain_id,
+      current_checkpoint_seq: config.starting_checkpoint_seq,
+      stop_checkpoint: config.stop_checkpoint,
+      source: config.source,
+      record_dir: config.record_dir,
+      download_queue_size: config.download_queue_size,
+      checkpoint_queue_size: config.checkpoint_queue_size,
+      batch_size: config.batch_size,
+      chain_id: config.chain_id,
+      output_format: config.output_format,
+      cursor_file: config.cursor_file,
+      gap_timeout: config.gap_timeout,
+      reorder_buffer_size: config.reorder_buffer_size,
+      backfill_workers: config.backfill_workers,
+      backfill_window: config.backfill_window,
+      shutdown: config.shutdown,
+      restart_policy: config.restart_policy,
+      module_resolver: config.module_resolver,
+      move_decoder,
+      decode_events: config.decode_events,
+      decode_pure_inputs: config.decode_pure_inputs,
+      decode_objects: config.decode_objects,
       metrics,
       logger,
     }
   }
 
+  /// Streams checkpoints until the stop checkpoint, if any, has been emitted or until a shutdown is requested.
+  /// Only returns Ok once every checkpoint it has committed to has been flushed to the sink.
   pub async fn start(&mut self) -> Result<()> {
-    // Format is FIRE INIT sui-node <PACKAGE_VERSION> <MAJOR_VERSION> <MINOR_VERSION> <CHAIN_ID>
-    self.logger.log(
-      &format!(
-        "\nFIRE INIT sui-node {} sui 0 0 {}",
-        env!("CARGO_PKG_VERSION"), self.chain_id,
-      ),
-    );
+    if let Some(stop_checkpoint) = self.stop_checkpoint {
+      ensure!(
+        self.current_checkpoint_seq <= stop_checkpoint,
+        "starting checkpoint {} is past the stop checkpoint {}",
+        self.current_checkpoint_seq,
+        stop_checkpoint,
+      );
+    }
 
-    let (
-      downloaded_checkpoint_data_sender,
-      downloaded_checkpoint_data_receiver
-    ) = channel(
-        DOWNLOAD_QUEUE_SIZE,
-        &get_metrics()
-        .unwrap()
-        .channels
-        .with_label_values(&["checkpoint_tx_downloading"]),
+    ensure!(
+      self.backfill_workers <= 1 || self.stop_checkpoint.is_some(),
+      "a parallel backfill with {} workers requires a stop checkpoint",
+      self.backfill_workers,
     );
 
-    self.spawn_fetcher(downloaded_checkpoint_data_sender).await?;
+    self.print_init().await?;
 
     let (
       handle_checkpoint_sender,
       handle_checkpoint_receiver
     ) = channel::<CheckpointDataToCommit>(
-      CHECKPOINT_QUEUE_SIZE,
+      self.checkpoint_queue_size,
       &get_metrics().unwrap()
       .channels
       .with_label_values(&["checkpoint_indexing"]),
     );
-    
-    self.spawn_checkpoint_handler(handle_checkpoint_sender, downloaded_checkpoint_data_receiver).await?;
-    self.commit_checkpoint_data(handle_checkpoint_receiver).await;
+
+    let (next_seq_sender, next_seq_receiver) = watch::channel(self.current_checkpoint_seq);
+
+    let mut pipeline = Pipeline {
+      handle_checkpoint_sender: Some(handle_checkpoint_sender),
+      next_seq: next_seq_receiver,
+      fetchers: JoinSet::new(),
+      handlers: JoinSet::new(),
+      refetch_sender: None,
+    };
+    self.spawn_pipeline(&mut pipeline).await?;
+
+    self.commit_checkpoint_data(handle_checkpoint_receiver, pipeline, next_seq_sender).await
+  }
+
+  /// Spawns fetchers and handlers that start from the next checkpoint to be emitted
+  async fn spawn_pipeline(&self, pipeline: &mut Pipeline) -> Result<()> {
+    if self.backfill_workers > 1 {
+      return self.spawn_backfill_workers(pipeline).await
+    }
+
+    let downloaded_checkpoint_data_sender = self.spawn_checkpoint_handler(pipeline).await?;
+    self.spawn_fetcher(pipeline, downloaded_checkpoint_data_sender.clone());
+    pipeline.refetch_sender = Some(downloaded_checkpoint_data_sender);
 
     Ok(())
   }
 
-  async fn spawn_fetcher(&self, downloaded_checkpoint_data_sender: Sender<CheckpointDownloadData>) -> Result<()> {
-    let http_client = retry(ExponentialBackoff::default(), || async {
-      let http_client = Self::get_http_client(&self.rpc_client_url).map_err(|err| {
-        error!("Failed to create HTTP client: {}", err);
-        err
-      })?;
-      
-      Ok(http_client)
-    }).await?;
-
-    let checkpoint_fetcher = CheckpointFetcher::new(
-      http_client,
-      Some(self.current_checkpoint_seq - 1),
+  fn spawn_fetcher(&self, pipeline: &mut Pipeline, downloaded_checkpoint_data_sender: Sender<CheckpointDownloadData>) {
+    if let Some(stop_checkpoint) = self.stop_checkpoint {
+      let range_fetcher = RangeFetcher::new(
+        self.source.clone(),
+        vec![self.current_checkpoint_seq..=stop_checkpoint],
+        None,
+        downloaded_checkpoint_data_sender,
+      );
+
+      spawn_supervised(&mut pipeline.fetchers, range_fetcher.run());
+
+      return
+    }
+
+    let tail_fetcher = TailFetcher::new(
+      self.source.clone(),
+      self.current_checkpoint_seq,
       downloaded_checkpoint_data_sender,
-      self.metrics.clone(),
     );
 
-    spawn(async move {
-      checkpoint_fetcher.run().await;
+    spawn_supervised(&mut pipeline.fetchers, tail_fetcher.run());
+  }
+
+  /// Downloads the given checkpoints again and pushes them back through the checkpoint handler. Whatever arrives
+  /// twice (because the fetcher eventually delivers it too) is dropped by the reorder buffer.
+  fn spawn_refetch(&self, pipeline: &mut Pipeline, missing: RangeInclusive<u64>) {
+    let Some(downloaded_checkpoint_data_sender) = pipeline.refetch_sender.clone() else {
+      return
+    };
+    let source = self.source.clone();
+    let backoff = ExponentialBackoff {
+      max_elapsed_time: Some(self.gap_timeout),
+      ..Default::default()
+    };
+
+    spawn_supervised(&mut pipeline.fetchers, async move {
+      for seq in missing {
+        // The commit loop reports the missing range once the gap timeout expires
+        let Ok(checkpoint_download_data) = download_checkpoint(&*source, seq, backoff.clone()).await else {
+          break
+        };
+
+        if downloaded_checkpoint_data_sender.send(checkpoint_download_data).await.is_err() {
+          break
+        }
+      }
+
+      Ok(())
     });
+  }
+
+  /// Every worker gets its own fetcher and checkpoint handler. They all feed the same commit loop which puts the
+  /// checkpoints back in order.
+  async fn spawn_backfill_workers(&self, pipeline: &mut Pipeline) -> Result<()> {
+    let stop_checkpoint = self.stop_checkpoint.ok_or_else(|| eyre!("parallel backfill requires a stop checkpoint"))?;
+    let chunk_size = (self.backfill_window / self.backfill_workers) as u64;
+    let worker_ranges = split_range(self.current_checkpoint_seq..=stop_checkpoint, self.backfill_workers, chunk_size);
+
+    info!("[fh-stream] backfilling {}..={} with {} workers", self.current_checkpoint_seq, stop_checkpoint, worker_ranges.len());
+
+    for ranges in worker_ranges {
+      let downloaded_checkpoint_data_sender = self.spawn_checkpoint_handler(pipeline).await?;
+      let window = Window {
+        next_seq: pipeline.next_seq.clone(),
+        size: self.backfill_window as u64,
+      };
+      let range_fetcher = RangeFetcher::new(
+        self.source.clone(), ranges, Some(window), downloaded_checkpoint_data_sender,
+      );
+
+      spawn_supervised(&mut pipeline.fetchers, range_fetcher.run());
+    }
 
     Ok(())
   }
 
-  async fn spawn_checkpoint_handler(
-    &self,
-    handle_checkpoint_sender: Sender<CheckpointDataToCommit>,
-    downloaded_checkpoint_data_receiver: Receiver<CheckpointDownloadData>
-  ) -> Result<()> {
-    let mut checkpoint_handler = self.create_handler(handle_checkpoint_sender).await?;
-    let stream = ReceiverStream::new(downloaded_checkpoint_data_receiver);
-    let mut chunks = stream.ready_chunks(CHECKPOINT_PROCESSING_BATCH_SIZE);
+  /// Spawns a checkpoint handler and returns the sender through which downloaded checkpoints can be fed to it
+  async fn spawn_checkpoint_handler(&self, pipeline: &mut Pipeline) -> Result<Sender<CheckpointDownloadData>> {
+    let handle_checkpoint_sender = pipeline.handle_checkpoint_sender.clone()
+    .ok_or_else(|| eyre!("cannot spawn a checkpoint handler while shutting down"))?;
+    let (
+      downloaded_checkpoint_data_sender,
+      downloaded_checkpoint_data_receiver
+    ) = channel(
+        self.download_queue_size,
+        &get_metrics()
+        .unwrap()
+        .channels
+        .with_label_values(&["checkpoint_tx_downloading"]),
+    );
 
-    spawn(async move {
-      while let Some(checkpoints) = chunks.next().await {
-        let checkpoint_data = checkpoints.iter().map(|c| c.data.clone()).collect::<Vec<_>>();
-        checkpoint_handler.process_checkpoints(&checkpoint_data).await.expect("process checkpoints"); 
+    let mut checkpoint_handler = self.create_handler(handle_checkpoint_sender).await?;
+    let mut stream = ReceiverStream::new(downloaded_checkpoint_data_receiver);
+    let mut batch_size = self.batch_size;
+    let record_dir = self.record_dir.clone();
+
+    spawn_supervised(&mut pipeline.handlers, async move {
+      while let Some(first) = stream.next().await {
+        // Never wait for a batch to fill up, just take whatever else is ready
+        let mut downloads = vec![first];
+        while downloads.len() < batch_size.current() {
+          let Some(Some(next)) = stream.next().now_or_never() else {
+            break
+          };
+          downloads.push(next);
+        }
+
+        // Encoding and writing the files would otherwise stall the runtime thread
+        let downloads = match record_dir.clone() {
+          Some(record_dir) => spawn_blocking(move || {
+            for checkpoint_download_data in &downloads {
+              record_dir.write_checkpoint(checkpoint_download_data)?;
+            }
+
+            Ok::<_, Report>(downloads)
+          }).await??,
+          None => downloads,
+        };
+
+        let checkpoint_data = downloads.into_iter().map(|download| download.data).collect::<Vec<_>>();
+
+        checkpoint_handler.process_checkpoints(&checkpoint_data).await
+        .map_err(|err| eyre!("could not process checkpoints: {}", err))?;
+        batch_size.update(checkpoint_data.len());
       }
+
+      Ok(())
     });
 
-    Ok(())
+    Ok(downloaded_checkpoint_data_sender)
   }
 
-  async fn commit_checkpoint_data(&mut self, handle_checkpoint_receiver: Receiver<CheckpointDataToCommit>) {
+  async fn commit_checkpoint_data(
+    &mut self,
+    handle_checkpoint_receiver: Receiver<CheckpointDataToCommit>,
+    mut pipeline: Pipeline,
+    next_seq: watch::Sender<u64>,
+  ) -> Result<()> {
     let mut stream = ReceiverStream::new(handle_checkpoint_receiver);
+    let mut shutdown = self.shutdown.clone();
+    let mut draining = false;
+    // Parallel workers never run further ahead than the window so that is all the buffer ever has to hold
+    let reorder_buffer_size = if self.backfill_workers > 1 {
+      self.backfill_window
+    } else {
+      self.reorder_buffer_size
+    };
+    let mut reorder_buffer = ReorderBuffer::new(self.current_checkpoint_seq, reorder_buffer_size);
+    let mut gap: Option<Gap> = None;
+    let mut restart_at: Option<Instant> = None;
+
+    loop {
+      // Nothing can fill a gap once the fetchers are gone so while draining we only wait for the queues to empty
+      let deadline = gap.as_ref().filter(|_| !draining).map(|gap| gap.deadline);
+      let next = select! {
+        Ok(_) = shutdown.wait_for(|shutdown| *shutdown), if !draining => {
+          info!("[fh-stream] shutting down, draining in-flight checkpoints");
+          draining = true;
+          restart_at = None;
+          pipeline.stop_fetchers();
+          continue;
+        },
+        failure = pipeline.failure(), if !draining && restart_at.is_none() => {
+          pipeline.abort();
+
+          let Some(delay) = self.restart_policy.next_restart() else {
+            return Err(failure).wrap_err_with(|| format!(
+              "giving up after the checkpoint pipeline failed {} times in a row",
+              self.restart_policy.consecutive_failures(),
+            ))
+          };
+
+          // Nothing is going to arrive before the restart so the gap timeout would only fire spuriously. The new
+          // fetcher starts right at the next checkpoint to be emitted which covers any open gap anyway.
+          gap = None;
+
+          error!(
+            "[fh-stream] checkpoint pipeline failed: {:?}. Restarting from checkpoint {} in {:?}",
+            failure, self.current_checkpoint_seq, delay,
+          );
+          restart_at = Some(Instant::now() + delay);
+          continue;
+        },
+        _ = sleep_until(restart_at.unwrap_or_else(Instant::now)), if restart_at.is_some() && !draining => {
+          restart_at = None;
+          self.spawn_pipeline(&mut pipeline).await?;
+          continue;
+        },
+        next = next_before(deadline, &mut stream) => next,
+      };
+
+      let Ok(next) = next else {
+        bail!(
+          "checkpoints {:?} are still missing after waiting for {:?}",
+          reorder_buffer.missing_range().unwrap_or(self.current_checkpoint_seq..=self.current_checkpoint_seq),
+          self.gap_timeout,
+        );
+      };
+
+      let Some(checkpoint_data) = next else {
+        ensure!(draining, "checkpoint stream ended unexpectedly before checkpoint {}", self.current_checkpoint_seq);
+
+        if !reorder_buffer.is_empty() {
+          warn!(
+            "[fh-stream] dropping {} checkpoints that arrived after the missing checkpoints {:?}",
+            reorder_buffer.len(),
+            reorder_buffer.missing_range(),
+          );
+        }
+
+        info!(
+          "[fh-stream] drained all in-flight checkpoints, the last emitted checkpoint is {}",
+          self.current_checkpoint_seq.saturating_sub(1),
+        );
+        return Ok(())
+      };
+
+      let seq = checkpoint_data.checkpoint.sequence_number;
+      // Once the buffer is full only the missing checkpoints are let in. The ones that are skipped are reported as a
+      // new gap right after the current one is filled and get refetched then, so a full buffer only fails the stream
+      // if the gap times out
+      if reorder_buffer.is_full() && !reorder_buffer.is_missing(seq) {
+        reorder_buffer.skip(seq);
+        debug!(
+          "[fh-stream] reorder buffer is full ({} checkpoints), dropping checkpoint {} until checkpoints {:?} arrive",
+          reorder_buffer.len(),
+          seq,
+          reorder_buffer.missing_range(),
+        );
+        continue;
+      }
 
-    while let Some(checkpoint_data) = stream.next().await {
-      // Convert and log data to the stdout
-      // We would need to ignore the following fields from CheckpointDataToCommit:
-      // 1. epoch
-      // 2. object_changes
-      // 3. object_history_changes
-      //
-      // These fields are computed and rely on state being stored which we don't want to do here.
-      //
-      // We will have to update the proto buf models and thus all convertsion logic that exist in the
-      // convert module.
-      assert!(self.current_checkpoint_seq == checkpoint_data.checkpoint.sequence_number, "sequence number mismatch");
-      self.logger.log(&format!("\nFIRE BLOCK_START {}", self.current_checkpoint_seq));
-
-      if checkpoint_data.transactions.is_empty() {
-        debug!("[fh-stream] no transactions to send");
+      if !reorder_buffer.push(seq, checkpoint_data) {
+        debug!("[fh-stream] dropping duplicate checkpoint {}", seq);
+        continue;
       }
 
-      debug!(
-        "[fh-stream] got {} transactions from  {}",
-        checkpoint_data.transactions.len(),
-        self.current_checkpoint_seq,
-      );
+      while let Some(checkpoint_data) = reorder_buffer.pop() {
+        self.commit_checkpoint(&checkpoint_data).await?;
+        self.restart_policy.reset();
+        next_seq.send_replace(self.current_checkpoint_seq);
 
-      self.print_checkpoint_overview(&convert_checkpoint(&checkpoint_data.checkpoint));
+        if self.reached_stop_checkpoint() {
+          info!("[fh-stream] reached stop checkpoint {}", self.current_checkpoint_seq - 1);
+          return Ok(())
+        }
+      }
 
-      for tx in &checkpoint_data.transactions {
-        let txn_proto = convert_transaction(&tx);
-        self.print_transaction(&txn_proto);
+      let Some(missing) = reorder_buffer.missing_range() else {
+        gap = None;
+        continue;
+      };
+
+      // The missing range can only shrink while its start stays the same so we only need to refetch when a new
+      // gap opens up
+      if gap.as_ref().map_or(true, |gap| gap.start != *missing.start()) {
+        gap = Some(Gap {
+          start: *missing.start(),
+          deadline: Instant::now() + self.gap_timeout,
+        });
+
+        if pipeline.refetch_sender.is_some() {
+          warn!("[fh-stream] checkpoints {:?} are missing, refetching", missing);
+          self.spawn_refetch(&mut pipeline, missing);
+        }
       }
+    }
+  }
 
-      let obj_changes_proto = convert_tx_object_changes(&checkpoint_data.object_changes);
-      self.print_object_changes(&obj_changes_proto);
+  fn reached_stop_checkpoint(&self) -> bool {
+    self.stop_checkpoint.map_or(false, |stop_checkpoint| self.current_checkpoint_seq > stop_checkpoint)
+  }
 
-      // Not that the transaction data does also include event data but here we explicitely log
-      // events if one is interested in just that
-      for event in &checkpoint_data.events {
-        let event_proto = convert_indexed_event(event);
-        self.print_event(&event_proto);
-      }
+  async fn commit_checkpoint(&mut self, checkpoint_data: &CheckpointDataToCommit) -> Result<()> {
+    if checkpoint_data.transactions.is_empty() {
+      debug!("[fh-stream] no transactions to send");
+    }
 
-      for (_, store_display) in &checkpoint_data.display_updates {
-        let store_display_proto = convert_display_update(store_display);
-        self.print_display_update(&store_display_proto);
-      }
+    debug!(
+      "[fh-stream] got {} transactions from  {}",
+      checkpoint_data.transactions.len(),
+      self.current_checkpoint_seq,
+    );
+
+    for package in &checkpoint_data.packages {
+      self.module_resolver.add_package(&package.move_package)?;
+    }
+
+    self.emit_checkpoint(checkpoint_data).await.wrap_err_with(|| format!(
+      "could not emit checkpoint {}; the last fully emitted checkpoint is {}",
+      self.current_checkpoint_seq,
+      self.current_checkpoint_seq.saturating_sub(1),
+    ))?;
+
+    self.persist_cursor().await.wrap_err_with(|| format!(
+      "could not persist cursor after checkpoint {}",
+      self.current_checkpoint_seq,
+    ))?;
+
+    self.current_checkpoint_seq += 1;
+
+    Ok(())
+  }
 
-      self.logger.log(&format!("\nFIRE BLOCK_END {}", self.current_checkpoint_seq));
-      self.current_checkpoint_seq += 1;
+  async fn emit_checkpoint(&mut self, checkpoint_data: &CheckpointDataToCommit) -> Result<()> {
+    match self.output_format {
+      OutputFormat::Lines => self.print_checkpoint_lines(checkpoint_data).await?,
+      OutputFormat::Block => self.print_block(checkpoint_data).await?,
     }
+
+    // A block is only considered emitted once it has been flushed all the way to the sink
+    self.logger.flush().await
+  }
+
+  async fn persist_cursor(&self) -> Result<()> {
+    let Some(cursor_file) = &self.cursor_file else {
+      return Ok(())
+    };
+
+    cursor_file.save(&Cursor {
+      chain_id: self.chain_id.clone(),
+      checkpoint_seq: self.current_checkpoint_seq,
+    }).await
   }
 
   async fn create_handler(&self, handle_checkpoint_sender: Sender<CheckpointDataToCommit>) -> Result<CheckpointHandler> {
@@ -205,14 +594,134 @@ where
     Ok(checkpoint_handler)
   }
 
-  fn get_http_client(rpc_client_url: &str) -> Result<Client> {
-    let rest_api_url = format!("{}/rest", rpc_client_url);
-    let rest_client = Client::new(&rest_api_url);
+  async fn print_init(&mut self) -> Result<()> {
+    match self.output_format {
+      OutputFormat::Lines => {
+        // Format is FIRE INIT sui-node <PACKAGE_VERSION> <MAJOR_VERSION> <MINOR_VERSION> <CHAIN_ID>
+        self.logger.log(
+          &format!(
+            "\nFIRE INIT sui-node {} sui 0 0 {}",
+            env!("CARGO_PKG_VERSION"), self.chain_id,
+          ),
+        ).await?;
+      },
+      OutputFormat::Block => {
+        // Format is FIRE INIT <PROTOCOL_VERSION> <BLOCK_TYPE_URL>
+        self.logger.log(&format!("\nFIRE INIT {} {}", FIRE_PROTOCOL_VERSION, BLOCK_TYPE_URL)).await?;
+      },
+    }
+
+    self.logger.flush().await
+  }
+
+  async fn print_checkpoint_lines(&mut self, checkpoint_data: &CheckpointDataToCommit) -> Result<()> {
+    // Convert and log data to the stdout
+    // We would need to ignore the following fields from CheckpointDataToCommit:
+    // 1. object_changes
+    // 2. object_history_changes
+    //
+    // These fields are computed and rely on state being stored which we don't want to do here. The epoch on the
+    // other hand is derived from the checkpoint itself so it is emitted on the checkpoint that closes an epoch.
+    //
+    // We will have to update the proto buf models and thus all convertsion logic that exist in the
+    // convert module.
+    self.logger.log(&format!("\nFIRE BLOCK_START {}", self.current_checkpoint_seq)).await?;
+    self.print_checkpoint_overview(&convert_checkpoint(&checkpoint_data.checkpoint)).await?;
+
+    for tx in &checkpoint_data.transactions {
+      let mut txn_proto = convert_transaction(&tx);
+      if self.decode_pure_inputs {
+        decode_pure_inputs(&self.move_decoder, tx, &mut txn_proto);
+      }
+      if self.decode_events {
+        decode_transaction_events(&self.move_decoder, tx, &mut txn_proto);
+      }
+      self.print_transaction(&txn_proto).await?;
+    }
+
+    for tx_index in &checkpoint_data.tx_indices {
+      let tx_index_proto = convert_tx_index(tx_index);
+      self.print_tx_index(&tx_index_proto).await?;
+    }
+
+    let mut obj_changes_proto = convert_tx_object_changes(&checkpoint_data.object_changes);
+    if let Some(filter) = &self.decode_objects {
+      decode_tx_object_changes(&self.move_decoder, filter, &checkpoint_data.object_changes, &mut obj_changes_proto);
+    }
+    self.print_object_changes(&obj_changes_proto).await?;
+
+    // Not that the transaction data does also include event data but here we explicitely log
+    // events if one is interested in just that
+    for event in &checkpoint_data.events {
+      let mut event_proto = convert_indexed_event(event);
+      if self.decode_events {
+        event_proto.decoded = Some(decode_indexed_event(&self.move_decoder, event));
+      }
+      self.print_event(&event_proto).await?;
+    }
+
+    for (_, store_display) in &checkpoint_data.display_updates {
+      let store_display_proto = convert_display_update(store_display);
+      self.print_display_update(&store_display_proto).await?;
+    }
+
+    for package_proto in convert_indexed_packages(&checkpoint_data.packages) {
+      self.print_package(&package_proto).await?;
+    }
 
-    Ok(rest_client)
+    if let Some(epoch) = &checkpoint_data.epoch {
+      self.print_epoch(&convert_epoch(epoch)).await?;
+    }
+
+    self.logger.log(&format!("\nFIRE BLOCK_END {}", self.current_checkpoint_seq)).await
   }
 
-  fn print_checkpoint_overview(&self, checkpoint: &pb::Checkpoint) {
+  async fn print_block(&mut self, checkpoint_data: &CheckpointDataToCommit) -> Result<()> {
+    let mut block = convert_checkpoint_data(checkpoint_data);
+    if self.decode_pure_inputs {
+      decode_checkpoint_data_pure_inputs(&self.move_decoder, checkpoint_data, &mut block);
+    }
+    if self.decode_events {
+      decode_checkpoint_data_events(&self.move_decoder, checkpoint_data, &mut block);
+    }
+    if let Some(filter) = &self.decode_objects {
+      if let Some(object_change) = block.object_change.as_mut() {
+        decode_tx_object_changes(&self.move_decoder, filter, &checkpoint_data.object_changes, object_change);
+      }
+    }
+    let mut buf = vec![];
+    block.encode(&mut buf).unwrap_or_else(|_| {
+      panic!(
+        "Could not convert protobuf checkpoint data to bytes '{:?}'",
+        block
+      )
+    });
+
+    let checkpoint = &checkpoint_data.checkpoint;
+    let parent_num = checkpoint.sequence_number.saturating_sub(1);
+    let parent_digest = checkpoint.previous_checkpoint_digest
+    .unwrap_or_else(|| CheckpointDigest::new([0; 32]))
+    .base58_encode();
+    // Certified checkpoints are final so every block is its own last irreversible block
+    let lib_num = checkpoint.sequence_number;
+    let timestamp_ns = checkpoint.timestamp_ms * 1_000_000;
+
+    // Format is FIRE BLOCK <NUM> <DIGEST> <PARENT_NUM> <PARENT_DIGEST> <LIB_NUM> <TIMESTAMP_NS> <BASE64_PAYLOAD>
+    // The genesis checkpoint has no parent so its <PARENT_DIGEST> is the all zero digest i.e. 32 times '1' in base58.
+    // An empty field would collapse two spaces and shift every field that follows
+    self.logger.log(&format!(
+      "\nFIRE BLOCK {} {} {} {} {} {} {}",
+      checkpoint.sequence_number,
+      checkpoint.checkpoint_digest.base58_encode(),
+      parent_num,
+      parent_digest,
+      lib_num,
+      timestamp_ns,
+      base64::encode(buf),
+    )).await
+  }
+
+  async fn print_checkpoint_overview(&mut self, checkpoint: &pb::Checkpoint) -> Result<()> {
     let mut buf = vec![];
     checkpoint.encode(&mut buf).unwrap_or_else(|_| {
       panic!(
@@ -221,10 +730,10 @@ where
       )
     });
 
-    self.logger.log(&format!("\nFIRE CHECKPOINT {}", base64::encode(buf)));
+    self.logger.log(&format!("\nFIRE CHECKPOINT {}", base64::encode(buf))).await
   }
 
-  fn print_transaction(&self, transaction: &pb::Transaction) {
+  async fn print_transaction(&mut self, transaction: &pb::Transaction) -> Result<()> {
     let mut buf = vec![];
     transaction.encode(&mut buf).unwrap_or_else(|_| {
       panic!(
@@ -233,10 +742,22 @@ where
       )
     });
 
-    self.logger.log(&format!("\nFIRE TRX {}", base64::encode(buf)));
+    self.logger.log(&format!("\nFIRE TRX {}", base64::encode(buf))).await
   }
 
-  fn print_object_changes(&self, tx_object_change: &pb::TransactionObjectChange) {
+  async fn print_tx_index(&mut self, tx_index: &pb::TxIndex) -> Result<()> {
+    let mut buf = vec![];
+    tx_index.encode(&mut buf).unwrap_or_else(|_| {
+      panic!(
+        "Could not convert protobuf tx index to bytes '{:?}'",
+        tx_index
+      )
+    });
+
+    self.logger.log(&format!("\nFIRE TX_INDEX {}", base64::encode(buf))).await
+  }
+
+  async fn print_object_changes(&mut self, tx_object_change: &pb::TransactionObjectChange) -> Result<()> {
     let mut buf = vec![];
     tx_object_change.encode(&mut buf).unwrap_or_else(|_| {
       panic!(
@@ -245,10 +766,10 @@ where
       )
     });
 
-    self.logger.log(&format!("\nFIRE OBJ_CHANGE {}", base64::encode(buf)));
+    self.logger.log(&format!("\nFIRE OBJ_CHANGE {}", base64::encode(buf))).await
   }
 
-  fn print_event(&self, event: &pb::IndexedEvent) {
+  async fn print_event(&mut self, event: &pb::IndexedEvent) -> Result<()> {
     let mut buf = vec![];
     event.encode(&mut buf).unwrap_or_else(|_| {
       panic!(
@@ -257,10 +778,34 @@ where
       )
     });
 
-    self.logger.log(&format!("\nFIRE EVT {}", base64::encode(buf)));
+    self.logger.log(&format!("\nFIRE EVT {}", base64::encode(buf))).await
   }
 
-  fn print_display_update(&self, display_update: &pb::StoredDisplay) {
+  async fn print_package(&mut self, package: &pb::IndexedPackage) -> Result<()> {
+    let mut buf = vec![];
+    package.encode(&mut buf).unwrap_or_else(|_| {
+      panic!(
+        "Could not convert protobuf package to bytes '{:?}'",
+        package
+      )
+    });
+
+    self.logger.log(&format!("\nFIRE PACKAGE {}", base64::encode(buf))).await
+  }
+
+  async fn print_epoch(&mut self, epoch: &pb::Epoch) -> Result<()> {
+    let mut buf = vec![];
+    epoch.encode(&mut buf).unwrap_or_else(|_| {
+      panic!(
+        "Could not convert protobuf epoch to bytes '{:?}'",
+        epoch
+      )
+    });
+
+    self.logger.log(&format!("\nFIRE EPOCH {}", base64::encode(buf))).await
+  }
+
+  async fn print_display_update(&mut self, display_update: &pb::StoredDisplay) -> Result<()> {
     let mut buf = vec![];
     display_update.encode(&mut buf).unwrap_or_else(|_| {
       panic!(
@@ -269,6 +814,17 @@ where
       )
     });
 
-    self.logger.log(&format!("\nFIRE DSP_UPDATE {}", base64::encode(buf)));
+    self.logger.log(&format!("\nFIRE DSP_UPDATE {}", base64::encode(buf))).await
+  }
+}
+
+/// Waits for the next item of the stream but gives up once the deadline, if any, has passed
+async fn next_before<S>(deadline: Option<Instant>, stream: &mut S) -> Result<Option<S::Item>, Elapsed>
+where
+  S: Stream + Unpin
+{
+  match deadline {
+    Some(deadline) => timeout_at(deadline, stream.next()).await,
+    None => Ok(stream.next().await),
   }
 }
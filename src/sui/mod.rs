@@ -1,3 +1,4 @@
 pub mod sui_node;
 pub mod sui_store;
 pub mod module_resolver;
+pub mod move_decoder;
@@ -1,13 +1,286 @@
-use move_core_types::resolver::ModuleResolver;
-use eyre::Report;
+use std::{
+  collections::HashMap, ffi::OsStr, fs, path::{Path, PathBuf}, str::FromStr,
+  sync::{atomic::{AtomicU64, Ordering}, Arc, RwLock},
+};
+use eyre::{Report, Result, WrapErr};
+use move_bytecode_utils::module_cache::SyncModuleCache;
+use move_core_types::{identifier::IdentStr, language_storage::ModuleId, resolver::ModuleResolver};
+use sui_framework::BuiltInFramework;
+use sui_types::{
+  base_types::ObjectID, move_package::MovePackage, MOVE_STDLIB_PACKAGE_ID, SUI_FRAMEWORK_PACKAGE_ID,
+  SUI_SYSTEM_PACKAGE_ID,
+};
+use crate::atomic_file::write_atomically;
 
-/// Dumy implementation of the ModuleResolver 
-pub struct SuiModuleResolver;
+const PACKAGE_FILE_EXTENSION: &str = "bcs";
+const SYSTEM_PACKAGE_IDS: [ObjectID; 3] = [MOVE_STDLIB_PACKAGE_ID, SUI_FRAMEWORK_PACKAGE_ID, SUI_SYSTEM_PACKAGE_ID];
+
+/// Resolves modules from the packages that have been published or upgraded in the stream so far. Packages are
+/// keyed by their storage id which is also the address of every type that was first defined in them. Modules
+/// refer to other packages, and to their own, by runtime id i.e. the id of the first version of the package, which
+/// `linked_package` and `type_origin` translate back to storage ids.
+/// If a cache directory is provided each package is written there as well so that it survives a restart.
+#[derive(Clone, Default)]
+pub struct SuiModuleResolver {
+  packages: Arc<RwLock<HashMap<ObjectID, MovePackage>>>,
+  /// Bumped every time a package replaces one with the same id
+  generation: Arc<AtomicU64>,
+  cache_dir: Option<PathBuf>,
+}
+
+impl SuiModuleResolver {
+  /// Loads every package that was previously written to the cache directory, creating it if needed
+  pub fn new(cache_dir: Option<PathBuf>) -> Result<Self> {
+    let mut packages = HashMap::new();
+
+    if let Some(cache_dir) = &cache_dir {
+      fs::create_dir_all(cache_dir)
+      .wrap_err_with(|| format!("could not create module cache {}", cache_dir.display()))?;
+
+      for entry in fs::read_dir(cache_dir)? {
+        let path = entry?.path();
+        if path.extension() != Some(OsStr::new(PACKAGE_FILE_EXTENSION)) {
+          continue
+        }
+
+        let Some(package_id) = path.file_stem().and_then(OsStr::to_str).and_then(|s| ObjectID::from_str(s).ok()) else {
+          continue
+        };
+        let content = fs::read(&path)?;
+        let package = bcs::from_bytes(&content)
+        .wrap_err_with(|| format!("could not load cached package {}", path.display()))?;
+
+        packages.insert(package_id, package);
+      }
+    }
+
+    Ok(Self {packages: Arc::new(RwLock::new(packages)), generation: Arc::default(), cache_dir})
+  }
+
+  /// Adds the Move stdlib (0x1), the Sui framework (0x2) and Sui system (0x3) packages that are embedded in the
+  /// binary unless a version of them has already been loaded from the cache
+  pub fn bootstrap_system_packages(&self) -> Result<()> {
+    let mut packages = self.packages.write().unwrap();
+
+    for system_package in BuiltInFramework::iter_system_packages() {
+      if !SYSTEM_PACKAGE_IDS.contains(system_package.id()) || packages.contains_key(system_package.id()) {
+        continue
+      }
+
+      packages.insert(*system_package.id(), system_package.genesis_move_package());
+    }
+
+    Ok(())
+  }
+
+  /// Makes the modules of a published or upgraded package resolvable. System packages keep their id when they
+  /// are upgraded in which case the new version replaces the previous one.
+  pub fn add_package(&self, package: &MovePackage) -> Result<()> {
+    let package_id = package.id();
+
+    if let Some(cache_dir) = &self.cache_dir {
+      persist_package(cache_dir, package)
+      .wrap_err_with(|| format!("could not cache package {}", package_id))?;
+    }
+
+    // Bumped while holding the lock so that no module of the new version can be cached under the previous
+    // generation
+    let mut packages = self.packages.write().unwrap();
+    if packages.insert(package_id, package.clone()).is_some() {
+      self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    Ok(())
+  }
+
+  pub fn generation(&self) -> u64 {
+    self.generation.load(Ordering::SeqCst)
+  }
+
+  /// Storage id of the version of a dependency that the given package was linked against. Packages that are not
+  /// in the linkage table, i.e. the system packages, are stored under their runtime id.
+  pub fn linked_package(&self, package_id: &ObjectID, runtime_id: &ObjectID) -> ObjectID {
+    let packages = self.packages.read().unwrap();
+
+    packages.get(package_id)
+    .and_then(|package| package.linkage_table().get(runtime_id))
+    .map_or(*runtime_id, |upgrade_info| upgrade_info.upgraded_id)
+  }
+
+  /// Id of the package that first defined the struct, i.e. the address of its type, given the storage id of any
+  /// later version of the package
+  pub fn type_origin(&self, package_id: &ObjectID, module: &IdentStr, name: &IdentStr) -> ObjectID {
+    let packages = self.packages.read().unwrap();
+
+    packages.get(package_id)
+    .and_then(|package| {
+      package.type_origin_table().iter()
+      .find(|origin| origin.module_name == module.as_str() && origin.struct_name == name.as_str())
+    })
+    .map_or(*package_id, |origin| origin.package)
+  }
+}
+
+/// Deserialized modules of the resolver. A package that replaces a previous version, i.e. a system package upgrade,
+/// starts a new cache so that the modules of the previous version are never served again. Clones share the cache.
+#[derive(Clone)]
+pub struct ModuleCache {
+  module_resolver: SuiModuleResolver,
+  current: Arc<RwLock<(u64, Arc<SyncModuleCache<SuiModuleResolver>>)>>,
+}
+
+impl ModuleCache {
+  pub fn new(module_resolver: SuiModuleResolver) -> Self {
+    let cache = Arc::new(SyncModuleCache::new(module_resolver.clone()));
+    let current = Arc::new(RwLock::new((module_resolver.generation(), cache)));
+
+    Self {module_resolver, current}
+  }
+
+  pub fn get(&self) -> Arc<SyncModuleCache<SuiModuleResolver>> {
+    let generation = self.module_resolver.generation();
+    {
+      let (cached_generation, cache) = &*self.current.read().unwrap();
+      if *cached_generation == generation {
+        return cache.clone()
+      }
+    }
+
+    let mut current = self.current.write().unwrap();
+    if current.0 != generation {
+      *current = (generation, Arc::new(SyncModuleCache::new(self.module_resolver.clone())));
+    }
+
+    current.1.clone()
+  }
+}
+
+fn persist_package(cache_dir: &Path, package: &MovePackage) -> Result<()> {
+  let path = cache_dir.join(format!("{}.{}", package.id(), PACKAGE_FILE_EXTENSION));
+
+  write_atomically(&path, &bcs::to_bytes(package)?)
+}
 
 impl ModuleResolver for SuiModuleResolver {
   type Error = Report;
 
-  fn get_module(&self, _: &move_core_types::language_storage::ModuleId) -> Result<Option<Vec<u8>>, Self::Error> {
-    Ok(None)
+  fn get_module(&self, id: &ModuleId) -> Result<Option<Vec<u8>>, Self::Error> {
+    let packages = self.packages.read().unwrap();
+    let module = packages.get(&ObjectID::from(*id.address()))
+    .and_then(|package| package.serialized_module_map().get(id.name().as_str()))
+    .cloned();
+
+    Ok(module)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{collections::BTreeMap, str::FromStr};
+  use move_bytecode_utils::module_cache::GetModule;
+  use move_core_types::{identifier::Identifier, language_storage::ModuleId, resolver::ModuleResolver};
+  use sui_types::{
+    base_types::{ObjectID, SequenceNumber}, move_package::{MovePackage, TypeOrigin, UpgradeInfo},
+    SUI_FRAMEWORK_PACKAGE_ID,
+  };
+  use crate::test_utils::TempDir;
+  use super::{ModuleCache, SuiModuleResolver};
+
+  fn module_id(package_id: ObjectID, name: &str) -> ModuleId {
+    ModuleId::new(package_id.into(), Identifier::new(name).unwrap())
+  }
+
+  #[test]
+  fn resolves_packages_across_restarts() {
+    let cache_dir = TempDir::new("module-cache");
+
+    let package_id = ObjectID::from_str("0x0000000000000000000000000000000000000000000000000000000000000abc").unwrap();
+    let mut module_map = BTreeMap::new();
+    module_map.insert("pool".to_string(), vec![1, 2, 3]);
+    let package = MovePackage::new(
+      package_id,
+      SequenceNumber::from_u64(1),
+      module_map,
+      u64::MAX,
+      vec![],
+      BTreeMap::new(),
+    ).unwrap();
+
+    let resolver = SuiModuleResolver::new(Some(cache_dir.path().to_path_buf())).unwrap();
+    assert_eq!(resolver.get_module(&module_id(package_id, "pool")).unwrap(), None);
+
+    resolver.add_package(&package).unwrap();
+    assert_eq!(resolver.get_module(&module_id(package_id, "pool")).unwrap(), Some(vec![1, 2, 3]));
+
+    let restarted = SuiModuleResolver::new(Some(cache_dir.path().to_path_buf())).unwrap();
+    assert_eq!(restarted.get_module(&module_id(package_id, "pool")).unwrap(), Some(vec![1, 2, 3]));
+    assert_eq!(restarted.get_module(&module_id(package_id, "other")).unwrap(), None);
+  }
+
+  #[test]
+  fn bootstraps_system_packages() {
+    let resolver = SuiModuleResolver::new(None).unwrap();
+    assert_eq!(resolver.get_module(&module_id(SUI_FRAMEWORK_PACKAGE_ID, "coin")).unwrap(), None);
+
+    resolver.bootstrap_system_packages().unwrap();
+    assert!(resolver.get_module(&module_id(SUI_FRAMEWORK_PACKAGE_ID, "coin")).unwrap().is_some());
+  }
+
+  #[test]
+  fn drops_cached_modules_of_replaced_packages() {
+    let resolver = SuiModuleResolver::new(None).unwrap();
+    resolver.bootstrap_system_packages().unwrap();
+    let module_cache = ModuleCache::new(resolver.clone());
+
+    let coin = module_id(SUI_FRAMEWORK_PACKAGE_ID, "coin");
+    assert!(module_cache.get().get_module_by_id(&coin).unwrap().is_some());
+
+    // An upgrade of the framework that no longer has the coin module
+    let object = resolver.get_module(&module_id(SUI_FRAMEWORK_PACKAGE_ID, "object")).unwrap().unwrap();
+    let upgrade = MovePackage::new(
+      SUI_FRAMEWORK_PACKAGE_ID,
+      SequenceNumber::from_u64(2),
+      BTreeMap::from([("object".to_string(), object)]),
+      u64::MAX,
+      vec![],
+      BTreeMap::new(),
+    ).unwrap();
+    resolver.add_package(&upgrade).unwrap();
+
+    assert!(module_cache.get().get_module_by_id(&coin).unwrap().is_none());
+  }
+
+  #[test]
+  fn resolves_types_through_the_linkage_and_type_origin_tables() {
+    let [original_id, upgraded_id, dependency_v1, dependency_v2] = ["0xa1", "0xa2", "0xb1", "0xb2"]
+    .map(|id| ObjectID::from_str(id).unwrap());
+    let origin = |module: &str, name: &str, package| TypeOrigin {
+      module_name: module.to_string(),
+      struct_name: name.to_string(),
+      package,
+    };
+
+    // The upgrade added Pool to a module that was already there and links against the second version of its
+    // dependency
+    let upgraded = MovePackage::new(
+      upgraded_id,
+      SequenceNumber::from_u64(2),
+      BTreeMap::from([("pool".to_string(), vec![1, 2, 3])]),
+      u64::MAX,
+      vec![origin("pool", "Config", original_id), origin("pool", "Pool", upgraded_id)],
+      BTreeMap::from([
+        (dependency_v1, UpgradeInfo {upgraded_id: dependency_v2, upgraded_version: SequenceNumber::from_u64(2)}),
+      ]),
+    ).unwrap();
+
+    let resolver = SuiModuleResolver::new(None).unwrap();
+    resolver.add_package(&upgraded).unwrap();
+
+    assert_eq!(resolver.linked_package(&upgraded_id, &dependency_v1), dependency_v2);
+    assert_eq!(resolver.linked_package(&upgraded_id, &SUI_FRAMEWORK_PACKAGE_ID), SUI_FRAMEWORK_PACKAGE_ID);
+
+    let pool = Identifier::new("pool").unwrap();
+    assert_eq!(resolver.type_origin(&upgraded_id, &pool, &Identifier::new("Config").unwrap()), original_id);
+    assert_eq!(resolver.type_origin(&upgraded_id, &pool, &Identifier::new("Pool").unwrap()), upgraded_id);
   }
 }
@@ -0,0 +1,251 @@
+use std::sync::Arc;
+use eyre::{bail, eyre, Result};
+use move_binary_format::{
+  access::ModuleAccess, file_format::{SignatureToken, StructFieldInformation, StructHandleIndex}, CompiledModule,
+};
+use move_bytecode_utils::module_cache::GetModule;
+use move_core_types::{
+  annotated_value::{MoveFieldLayout, MoveStructLayout, MoveTypeLayout, MoveValue}, identifier::IdentStr,
+  language_storage::{ModuleId, StructTag, TypeTag},
+};
+use sui_types::base_types::ObjectID;
+use super::module_resolver::{ModuleCache, SuiModuleResolver};
+
+/// Decodes BCS encoded Move values using the type layouts of the packages known to the module resolver. Clones
+/// share the same cache of deserialized modules.
+#[derive(Clone)]
+pub struct MoveDecoder {
+  module_resolver: SuiModuleResolver,
+  module_cache: ModuleCache,
+}
+
+impl MoveDecoder {
+  pub fn new(module_resolver: SuiModuleResolver) -> Self {
+    Self {module_cache: ModuleCache::new(module_resolver.clone()), module_resolver}
+  }
+
+  pub fn decode(&self, type_tag: &TypeTag, bytes: &[u8]) -> Result<MoveValue> {
+    let layout = self.type_layout(type_tag)
+    .map_err(|err| eyre!("could not resolve the layout of {}: {}", type_tag, err))?;
+
+    MoveValue::simple_deserialize(bytes, &layout)
+    .map_err(|err| eyre!("could not deserialize {}: {}", type_tag, err))
+  }
+
+  pub fn decode_struct(&self, struct_tag: &StructTag, bytes: &[u8]) -> Result<MoveValue> {
+    self.decode(&TypeTag::Struct(Box::new(struct_tag.clone())), bytes)
+  }
+
+  /// Parameter types of a function with its type parameters replaced by the given type arguments. References are
+  /// replaced by the type they point to. A parameter is None if its type can't be expressed as a TypeTag, e.g. a
+  /// type parameter without a matching type argument.
+  pub fn function_parameters(
+    &self,
+    package: &ObjectID,
+    module: &IdentStr,
+    function: &IdentStr,
+    type_arguments: &[TypeTag],
+  ) -> Result<Vec<Option<TypeTag>>> {
+    let compiled_module = self.module(package, module)?;
+
+    let function_handle = compiled_module.function_defs().iter()
+    .map(|function_def| compiled_module.function_handle_at(function_def.function))
+    .find(|function_handle| compiled_module.identifier_at(function_handle.name) == function)
+    .ok_or_else(|| eyre!("function {}::{}::{} not found", package, module, function))?;
+
+    let parameters = compiled_module.signature_at(function_handle.parameters).0.iter()
+    .map(|token| self.signature_token_to_type_tag(package, &compiled_module, token, type_arguments))
+    .collect();
+
+    Ok(parameters)
+  }
+
+  /// A module of the package with the given storage id
+  fn module(&self, package: &ObjectID, module: &IdentStr) -> Result<Arc<CompiledModule>> {
+    let module_id = ModuleId::new((*package).into(), module.to_owned());
+
+    self.module_cache.get().get_module_by_id(&module_id)
+    .map_err(|err| eyre!("could not load module {}: {}", module_id, err))?
+    .ok_or_else(|| eyre!("module {} not found", module_id))
+  }
+
+  fn type_layout(&self, type_tag: &TypeTag) -> Result<MoveTypeLayout> {
+    let layout = match type_tag {
+      TypeTag::Bool => MoveTypeLayout::Bool,
+      TypeTag::U8 => MoveTypeLayout::U8,
+      TypeTag::U16 => MoveTypeLayout::U16,
+      TypeTag::U32 => MoveTypeLayout::U32,
+      TypeTag::U64 => MoveTypeLayout::U64,
+      TypeTag::U128 => MoveTypeLayout::U128,
+      TypeTag::U256 => MoveTypeLayout::U256,
+      TypeTag::Address => MoveTypeLayout::Address,
+      TypeTag::Signer => MoveTypeLayout::Signer,
+      TypeTag::Vector(inner) => MoveTypeLayout::Vector(Box::new(self.type_layout(inner)?)),
+      TypeTag::Struct(struct_tag) => MoveTypeLayout::Struct(self.struct_layout(struct_tag)?),
+    };
+
+    Ok(layout)
+  }
+
+  /// Types are named after the package that first defined them. That version is enough to lay them out since
+  /// upgrades can't change existing structs, and the types of the fields are resolved through its linkage table so
+  /// they come from the exact versions it was built against.
+  fn struct_layout(&self, struct_tag: &StructTag) -> Result<MoveStructLayout> {
+    let package = ObjectID::from(struct_tag.address);
+    let compiled_module = self.module(&package, &struct_tag.module)?;
+
+    let struct_def = compiled_module.struct_defs().iter()
+    .find(|struct_def| {
+      let struct_handle = compiled_module.struct_handle_at(struct_def.struct_handle);
+      compiled_module.identifier_at(struct_handle.name) == struct_tag.name.as_ident_str()
+    })
+    .ok_or_else(|| eyre!("struct {} not found", struct_tag))?;
+
+    let StructFieldInformation::Declared(field_defs) = &struct_def.field_information else {
+      bail!("struct {} is native", struct_tag)
+    };
+
+    let fields = field_defs.iter()
+    .map(|field_def| {
+      let name = compiled_module.identifier_at(field_def.name);
+      let type_tag = self.signature_token_to_type_tag(
+        &package, &compiled_module, &field_def.signature.0, &struct_tag.type_params,
+      )
+      .ok_or_else(|| eyre!("field {} of {} has no concrete type", name, struct_tag))?;
+
+      Ok(MoveFieldLayout::new(name.to_owned(), self.type_layout(&type_tag)?))
+    })
+    .collect::<Result<Vec<_>>>()?;
+
+    Ok(MoveStructLayout {type_: struct_tag.clone(), fields})
+  }
+
+  /// Converts a type in a module of the package with the given storage id. Structs are named after the package
+  /// that first defined them, just like the type tags found in the chain data.
+  fn signature_token_to_type_tag(
+    &self,
+    package: &ObjectID,
+    module: &CompiledModule,
+    token: &SignatureToken,
+    type_arguments: &[TypeTag],
+  ) -> Option<TypeTag> {
+    let type_tag = match token {
+      SignatureToken::Bool => TypeTag::Bool,
+      SignatureToken::U8 => TypeTag::U8,
+      SignatureToken::U16 => TypeTag::U16,
+      SignatureToken::U32 => TypeTag::U32,
+      SignatureToken::U64 => TypeTag::U64,
+      SignatureToken::U128 => TypeTag::U128,
+      SignatureToken::U256 => TypeTag::U256,
+      SignatureToken::Address => TypeTag::Address,
+      SignatureToken::Signer => TypeTag::Signer,
+      SignatureToken::Vector(inner) => {
+        TypeTag::Vector(Box::new(self.signature_token_to_type_tag(package, module, inner, type_arguments)?))
+      },
+      SignatureToken::Struct(handle) => TypeTag::Struct(Box::new(self.struct_tag(package, module, *handle, vec![]))),
+      SignatureToken::StructInstantiation(instantiation) => {
+        let (handle, type_params) = &**instantiation;
+        let type_params = type_params.iter()
+        .map(|type_param| self.signature_token_to_type_tag(package, module, type_param, type_arguments))
+        .collect::<Option<Vec<_>>>()?;
+
+        TypeTag::Struct(Box::new(self.struct_tag(package, module, *handle, type_params)))
+      },
+      SignatureToken::Reference(inner) | SignatureToken::MutableReference(inner) => {
+        return self.signature_token_to_type_tag(package, module, inner, type_arguments)
+      },
+      SignatureToken::TypeParameter(index) => type_arguments.get(*index as usize)?.clone(),
+    };
+
+    Some(type_tag)
+  }
+
+  /// The module refers to the package that declares the struct by its runtime id which is the storage id of its
+  /// first version. It is translated to the version the module was linked against and from there to the version that
+  /// first defined the struct.
+  fn struct_tag(
+    &self,
+    package: &ObjectID,
+    module: &CompiledModule,
+    handle: StructHandleIndex,
+    type_params: Vec<TypeTag>,
+  ) -> StructTag {
+    let struct_handle = module.struct_handle_at(handle);
+    let module_handle = module.module_handle_at(struct_handle.module);
+    let runtime_id = ObjectID::from(*module.address_identifier_at(module_handle.address));
+    let module_name = module.identifier_at(module_handle.name);
+    let name = module.identifier_at(struct_handle.name);
+
+    let linked_package = if runtime_id == ObjectID::from(*module.address()) {
+      *package
+    } else {
+      self.module_resolver.linked_package(package, &runtime_id)
+    };
+
+    StructTag {
+      address: self.module_resolver.type_origin(&linked_package, module_name, name).into(),
+      module: module_name.to_owned(),
+      name: name.to_owned(),
+      type_params,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::str::FromStr;
+  use move_core_types::{
+    account_address::AccountAddress, annotated_value::{MoveStruct, MoveValue}, identifier::Identifier,
+    language_storage::{StructTag, TypeTag},
+  };
+  use sui_types::base_types::ObjectID;
+  use crate::{sui::module_resolver::SuiModuleResolver, test_utils::system_move_decoder};
+  use super::MoveDecoder;
+
+  fn object_id_tag(address: &str) -> StructTag {
+    StructTag {
+      address: AccountAddress::from_str(address).unwrap(),
+      module: Identifier::new("object").unwrap(),
+      name: Identifier::new("ID").unwrap(),
+      type_params: vec![],
+    }
+  }
+
+  #[test]
+  fn decodes_struct_with_system_package_layout() {
+    let decoder = system_move_decoder();
+
+    let struct_tag = object_id_tag("0x2");
+    let decoded = decoder.decode_struct(&struct_tag, &[7; 32]).unwrap();
+
+    assert_eq!(decoded, MoveValue::Struct(MoveStruct {
+      type_: struct_tag,
+      fields: vec![(Identifier::new("bytes").unwrap(), MoveValue::Address(AccountAddress::new([7; 32])))],
+    }));
+  }
+
+  #[test]
+  fn resolves_function_parameters() {
+    let decoder = system_move_decoder();
+
+    let parameters = decoder.function_parameters(
+      &ObjectID::from_str("0x2").unwrap(),
+      &Identifier::new("pay").unwrap(),
+      &Identifier::new("split").unwrap(),
+      &[TypeTag::from_str("0x2::sui::SUI").unwrap()],
+    ).unwrap();
+
+    assert_eq!(parameters, vec![
+      Some(TypeTag::from_str("0x2::coin::Coin<0x2::sui::SUI>").unwrap()),
+      Some(TypeTag::U64),
+      Some(TypeTag::from_str("0x2::tx_context::TxContext").unwrap()),
+    ]);
+  }
+
+  #[test]
+  fn fails_to_decode_types_of_unknown_packages() {
+    let decoder = MoveDecoder::new(SuiModuleResolver::new(None).unwrap());
+
+    assert!(decoder.decode_struct(&object_id_tag("0xabc"), &[7; 32]).is_err());
+  }
+}
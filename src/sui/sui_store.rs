@@ -9,19 +9,19 @@ use sui_indexer::{
   types::{IndexedCheckpoint, IndexedTransaction, IndexedEvent, IndexedPackage, TxIndex},
   models::display::StoredDisplay,
 };
-use super::module_resolver::SuiModuleResolver;
+use super::module_resolver::{ModuleCache, SuiModuleResolver};
 
 /// Dummy implementation of the IndexerStore trait. This is currently required by the CheckpointHandler we're utilizing
 /// from the sui-indexer crate. In theory we don't need to use this but since the core SUI indexer has a dependency on this
 /// trait we just pass a dummy NULL implementation.
 #[derive(Clone)]
 pub struct SuiStore {
-  module_cache: Arc<SyncModuleCache<SuiModuleResolver>>,
+  module_cache: ModuleCache,
 }
 
 impl SuiStore {
-  pub fn new() -> Self {
-    let module_cache = Arc::new(SyncModuleCache::new(SuiModuleResolver));
+  pub fn new(module_resolver: SuiModuleResolver) -> Self {
+    let module_cache = ModuleCache::new(module_resolver);
 
     Self {module_cache}
   }
@@ -92,7 +92,7 @@ impl IndexerStore for SuiStore {
   }
 
   fn module_cache(&self) -> Arc<Self::ModuleCache> {
-    self.module_cache.clone()
+    self.module_cache.get()
   }
 
   fn as_any(&self) -> &dyn Any {
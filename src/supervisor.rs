@@ -0,0 +1,103 @@
+use std::{future::Future, time::Duration};
+use backoff::{backoff::Backoff, ExponentialBackoff};
+use eyre::Result;
+use tokio::task::JoinSet;
+
+tokio::task_local! {
+  static SUPERVISED: ();
+}
+
+/// Spawns a task whose failures, panics included, are handled by whoever joins the set rather than by the global
+/// panic hook
+pub fn spawn_supervised<F>(tasks: &mut JoinSet<Result<()>>, task: F)
+where
+  F: Future<Output = Result<()>> + Send + 'static
+{
+  tasks.spawn(SUPERVISED.scope((), task));
+}
+
+/// True when called from within a task spawned with `spawn_supervised`. The panic hook runs on the panicking task
+/// so this tells it whether the panic is going to be recovered from.
+pub fn is_supervised() -> bool {
+  SUPERVISED.try_with(|_| ()).is_ok()
+}
+
+/// Decides whether and when a failed task should be restarted
+pub struct RestartPolicy {
+  max_consecutive_failures: u32,
+  consecutive_failures: u32,
+  backoff: ExponentialBackoff,
+}
+
+impl RestartPolicy {
+  pub fn new(max_consecutive_failures: u32) -> Self {
+    Self {
+      max_consecutive_failures,
+      consecutive_failures: 0,
+      // We count failures instead of giving up after a fixed amount of time
+      backoff: ExponentialBackoff {
+        max_elapsed_time: None,
+        ..Default::default()
+      },
+    }
+  }
+
+  /// Records a failure and returns how long to wait before restarting, or None once there have been too many
+  /// failures in a row
+  pub fn next_restart(&mut self) -> Option<Duration> {
+    self.consecutive_failures += 1;
+
+    if self.consecutive_failures > self.max_consecutive_failures {
+      return None
+    }
+
+    self.backoff.next_backoff()
+  }
+
+  /// Called once the restarted task has made progress
+  pub fn reset(&mut self) {
+    if self.consecutive_failures > 0 {
+      self.consecutive_failures = 0;
+      self.backoff.reset();
+    }
+  }
+
+  pub fn consecutive_failures(&self) -> u32 {
+    self.consecutive_failures
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use eyre::bail;
+  use tokio::task::JoinSet;
+  use super::{is_supervised, spawn_supervised, RestartPolicy};
+
+  #[test]
+  fn gives_up_after_max_consecutive_failures() {
+    let mut policy = RestartPolicy::new(2);
+
+    assert!(policy.next_restart().is_some());
+    assert!(policy.next_restart().is_some());
+    assert!(policy.next_restart().is_none());
+
+    policy.reset();
+    assert_eq!(policy.consecutive_failures(), 0);
+    assert!(policy.next_restart().is_some());
+  }
+
+  #[tokio::test]
+  async fn supervised_tasks_are_flagged() {
+    let mut tasks = JoinSet::new();
+    spawn_supervised(&mut tasks, async {
+      if is_supervised() {
+        bail!("supervised");
+      }
+
+      Ok(())
+    });
+
+    assert!(!is_supervised());
+    assert!(tasks.join_next().await.unwrap().unwrap().is_err());
+  }
+}
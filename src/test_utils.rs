@@ -0,0 +1,36 @@
+use std::{env, fs, path::{Path, PathBuf}, process};
+use crate::sui::{module_resolver::SuiModuleResolver, move_decoder::MoveDecoder};
+
+/// A decoder that knows about the system packages only
+pub fn system_move_decoder() -> MoveDecoder {
+  let module_resolver = SuiModuleResolver::new(None).unwrap();
+  module_resolver.bootstrap_system_packages().unwrap();
+
+  MoveDecoder::new(module_resolver)
+}
+
+/// A directory under the system temp dir that is removed when dropped, even if the test panics
+pub struct TempDir {
+  path: PathBuf,
+}
+
+impl TempDir {
+  /// The process id keeps concurrent test runs apart and the name keeps tests of the same run apart
+  pub fn new(name: &str) -> Self {
+    let path = env::temp_dir().join(format!("sf-sui-{}-{}", name, process::id()));
+    let _ = fs::remove_dir_all(&path);
+    fs::create_dir_all(&path).unwrap();
+
+    Self {path}
+  }
+
+  pub fn path(&self) -> &Path {
+    &self.path
+  }
+}
+
+impl Drop for TempDir {
+  fn drop(&mut self) {
+    let _ = fs::remove_dir_all(&self.path);
+  }
+}
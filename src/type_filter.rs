@@ -0,0 +1,117 @@
+use std::str::FromStr;
+use eyre::{bail, eyre, Report, Result, WrapErr};
+use move_core_types::{account_address::AccountAddress, identifier::Identifier, language_storage::StructTag};
+
+/// Matches every type of a package (`0x2`), of a module (`0x2::coin`) or a single struct regardless of its type
+/// parameters (`0x2::coin::Coin`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TypePattern {
+  Package(AccountAddress),
+  Module(AccountAddress, Identifier),
+  Struct(AccountAddress, Identifier, Identifier),
+}
+
+impl TypePattern {
+  fn matches(&self, struct_tag: &StructTag) -> bool {
+    match self {
+      TypePattern::Package(address) => struct_tag.address == *address,
+      TypePattern::Module(address, module) => struct_tag.address == *address && struct_tag.module == *module,
+      TypePattern::Struct(address, module, name) => {
+        struct_tag.address == *address && struct_tag.module == *module && struct_tag.name == *name
+      },
+    }
+  }
+}
+
+impl FromStr for TypePattern {
+  type Err = Report;
+
+  fn from_str(s: &str) -> Result<Self> {
+    let parts = s.split("::").collect::<Vec<_>>();
+    let address = AccountAddress::from_str(parts[0]).map_err(|err| eyre!("{}", err))?;
+
+    let pattern = match parts[1..] {
+      [] => TypePattern::Package(address),
+      [module] => TypePattern::Module(address, identifier(module)?),
+      [module, name] => TypePattern::Struct(address, identifier(module)?, identifier(name)?),
+      _ => bail!("expected <address>[::<module>[::<struct>]]"),
+    };
+
+    Ok(pattern)
+  }
+}
+
+fn identifier(s: &str) -> Result<Identifier> {
+  Identifier::new(s).map_err(|err| eyre!("{}", err))
+}
+
+/// Decides which Move types are worth decoding. A type is decoded if it doesn't match any of the deny patterns and
+/// matches one of the allow patterns, or there are no allow patterns at all.
+#[derive(Debug, Clone, Default)]
+pub struct TypeFilter {
+  allow: Vec<TypePattern>,
+  deny: Vec<TypePattern>,
+}
+
+impl TypeFilter {
+  pub fn new(allow: &[String], deny: &[String]) -> Result<Self> {
+    Ok(Self {
+      allow: parse_patterns(allow)?,
+      deny: parse_patterns(deny)?,
+    })
+  }
+
+  pub fn is_allowed(&self, struct_tag: &StructTag) -> bool {
+    if self.deny.iter().any(|pattern| pattern.matches(struct_tag)) {
+      return false
+    }
+
+    self.allow.is_empty() || self.allow.iter().any(|pattern| pattern.matches(struct_tag))
+  }
+}
+
+fn parse_patterns(patterns: &[String]) -> Result<Vec<TypePattern>> {
+  patterns.iter()
+  .map(|pattern| pattern.parse().wrap_err_with(|| format!("invalid type pattern {}", pattern)))
+  .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use std::str::FromStr;
+  use move_core_types::language_storage::StructTag;
+  use super::TypeFilter;
+
+  fn patterns(patterns: &[&str]) -> Vec<String> {
+    patterns.iter().map(|pattern| pattern.to_string()).collect()
+  }
+
+  #[test]
+  fn allows_everything_that_is_not_denied() {
+    let filter = TypeFilter::new(&[], &patterns(&["0x2::coin::Coin", "0xdee9"])).unwrap();
+
+    assert!(filter.is_allowed(&StructTag::from_str("0x2::clock::Clock").unwrap()));
+    assert!(!filter.is_allowed(&StructTag::from_str("0x2::coin::Coin<0x2::sui::SUI>").unwrap()));
+    assert!(!filter.is_allowed(&StructTag::from_str("0xdee9::clob_v2::Pool<0x2::sui::SUI, 0x2::sui::SUI>").unwrap()));
+  }
+
+  #[test]
+  fn only_allows_matching_types_unless_denied() {
+    let filter = TypeFilter::new(
+      &patterns(&["0x3::staking_pool", "0x2::clock::Clock"]),
+      &patterns(&["0x3::staking_pool::PoolTokenExchangeRate"]),
+    ).unwrap();
+
+    assert!(filter.is_allowed(&StructTag::from_str("0x3::staking_pool::StakedSui").unwrap()));
+    assert!(filter.is_allowed(&StructTag::from_str("0x2::clock::Clock").unwrap()));
+    assert!(!filter.is_allowed(&StructTag::from_str("0x3::staking_pool::PoolTokenExchangeRate").unwrap()));
+    assert!(!filter.is_allowed(&StructTag::from_str("0x2::coin::Coin<0x2::sui::SUI>").unwrap()));
+  }
+
+  #[test]
+  fn rejects_invalid_patterns() {
+    assert!(TypeFilter::new(&patterns(&["0x2::coin::Coin<0x2::sui::SUI>"]), &[]).is_err());
+    assert!(TypeFilter::new(&patterns(&["not_an_address"]), &[]).is_err());
+    assert!(TypeFilter::new(&patterns(&["0x2::a::b::c"]), &[]).is_err());
+  }
+}
@@ -0,0 +1,356 @@
+use std::{collections::BTreeMap, str::FromStr};
+use move_core_types::language_storage::{StructTag, TypeTag};
+use sui_protocol_config::ProtocolVersion;
+use sui_types::{
+  balance::Balance,
+  base_types::{AuthorityName, ExecutionDigests, ObjectID, ObjectRef, SequenceNumber, SuiAddress},
+  collection_types::{Bag, Table, TableVec, VecMap},
+  committee::Committee,
+  crypto::{get_key_pair, AccountKeyPair, AuthorityKeyPair, KeypairTraits},
+  digests::{CheckpointDigest, ObjectDigest, TransactionDigest},
+  dynamic_field::{derive_dynamic_field_id, Field},
+  effects::{TransactionEffects, TransactionEvents},
+  event::{Event, SystemEpochInfoEvent},
+  execution_status::ExecutionStatus,
+  full_checkpoint_content::{CheckpointData, CheckpointTransaction},
+  gas::GasCostSummary,
+  id::UID,
+  message_envelope::Message,
+  messages_checkpoint::{
+    CertifiedCheckpointSummary, CheckpointContents, CheckpointSummary, EndOfEpochData, SignedCheckpointSummary,
+  },
+  move_package::MovePackage,
+  object::{Data, MoveObject, Object, Owner, OBJECT_START_VERSION},
+  sui_system_state::{
+    sui_system_state_inner_v1::{
+      StakeSubsidyV1, StorageFundV1, SuiSystemStateInnerV1, SystemParametersV1, ValidatorSetV1,
+    },
+    SuiSystemStateWrapper,
+  },
+  transaction::{TransactionData, VerifiedTransaction},
+  utils::to_sender_signed_transaction,
+  Identifier, SUI_SYSTEM_ADDRESS, SUI_SYSTEM_STATE_OBJECT_ID,
+};
+
+const FIXTURE_PACKAGE_ID: &str = "0xf1";
+const GAS_OBJECT_ID: &str = "0xf2";
+const GAS_PRICE: u64 = 1000;
+
+/// Checkpoints chained through their previous digest and certified by a single validator. Everything happens in
+/// epoch 0 until a checkpoint closes it.
+pub struct FixtureChain {
+  committee: Committee,
+  key_pair: AuthorityKeyPair,
+  previous_digest: Option<CheckpointDigest>,
+  network_total_transactions: u64,
+}
+
+impl FixtureChain {
+  pub fn new() -> Self {
+    let (committee, mut key_pairs) = Committee::new_simple_test_committee_of_size(1);
+
+    Self {
+      committee,
+      key_pair: key_pairs.remove(0),
+      previous_digest: None,
+      network_total_transactions: 0,
+    }
+  }
+
+  pub fn checkpoint(
+    &mut self,
+    seq: u64,
+    transactions: Vec<CheckpointTransaction>,
+    end_of_epoch_data: Option<EndOfEpochData>,
+  ) -> CheckpointData {
+    let checkpoint_contents = CheckpointContents::new_with_digests_and_signatures(
+      transactions.iter()
+      .map(|tx| ExecutionDigests::new(*tx.transaction.digest(), tx.effects.digest())),
+      transactions.iter().map(|tx| tx.transaction.data().tx_signatures().to_vec()).collect(),
+    );
+    self.network_total_transactions += checkpoint_contents.size() as u64;
+
+    let summary = CheckpointSummary::new(
+      0,
+      seq,
+      self.network_total_transactions,
+      &checkpoint_contents,
+      self.previous_digest,
+      GasCostSummary::default(),
+      end_of_epoch_data,
+      1_700_000_000_000 + seq * 1000,
+    );
+
+    let authority = AuthorityName::from(self.key_pair.public());
+    let signed = SignedCheckpointSummary::new(0, summary.clone(), &self.key_pair, authority);
+    let checkpoint_summary = CertifiedCheckpointSummary::new(summary, vec![signed.auth_sig().clone()], &self.committee)
+    .unwrap();
+    self.previous_digest = Some(*checkpoint_summary.digest());
+
+    CheckpointData {checkpoint_summary, checkpoint_contents, transactions}
+  }
+}
+
+/// Checkpoints without any transaction
+pub fn fixture_checkpoints(first: u64, last: u64) -> Vec<CheckpointData> {
+  let mut chain = FixtureChain::new();
+
+  (first..=last).map(|seq| chain.checkpoint(seq, vec![], None)).collect()
+}
+
+/// A quiet checkpoint, one with a transaction that publishes a package and one that closes epoch 0
+pub fn fixture_checkpoints_with_activity(first: u64) -> Vec<CheckpointData> {
+  let mut chain = FixtureChain::new();
+  let end_of_epoch_data = EndOfEpochData {
+    next_epoch_committee: vec![],
+    next_epoch_protocol_version: ProtocolVersion::MAX,
+    epoch_commitments: vec![],
+  };
+
+  vec![
+    chain.checkpoint(first, vec![], None),
+    chain.checkpoint(first + 1, vec![publish_transaction()], None),
+    chain.checkpoint(first + 2, vec![change_epoch_transaction(1)], Some(end_of_epoch_data)),
+  ]
+}
+
+fn object_ref(object: &Object) -> ObjectRef {
+  object.compute_object_reference()
+}
+
+fn effects(
+  transaction_digest: TransactionDigest,
+  modified_at_versions: Vec<(ObjectID, SequenceNumber)>,
+  shared_objects: Vec<ObjectRef>,
+  created: Vec<(ObjectRef, Owner)>,
+  mutated: Vec<(ObjectRef, Owner)>,
+  gas_object: (ObjectRef, Owner),
+  events: Option<&TransactionEvents>,
+) -> TransactionEffects {
+  TransactionEffects::new_from_execution_v1(
+    ExecutionStatus::Success,
+    0,
+    GasCostSummary::default(),
+    modified_at_versions,
+    shared_objects,
+    transaction_digest,
+    created,
+    mutated,
+    vec![],
+    vec![],
+    vec![],
+    vec![],
+    gas_object,
+    events.map(TransactionEvents::digest),
+    vec![],
+  )
+}
+
+/// Publishes a package made of a single module. The module is never deserialized on the way to the output so
+/// its bytes don't need to be valid bytecode.
+fn publish_transaction() -> CheckpointTransaction {
+  let (sender, key_pair): (SuiAddress, AccountKeyPair) = get_key_pair();
+  let gas_id = ObjectID::from_str(GAS_OBJECT_ID).unwrap();
+  let gas = Object::with_id_owner_version_for_testing(gas_id, OBJECT_START_VERSION, sender);
+  let modules = BTreeMap::from([("fixture".to_string(), vec![1, 2, 3])]);
+
+  let data = TransactionData::new_module(
+    sender,
+    object_ref(&gas),
+    modules.values().cloned().collect(),
+    vec![],
+    GAS_PRICE * 10_000,
+    GAS_PRICE,
+  );
+  let transaction = to_sender_signed_transaction(data, &key_pair);
+
+  let lamport_version = SequenceNumber::from_u64(OBJECT_START_VERSION.value() + 1);
+  let package = MovePackage::new(
+    ObjectID::from_str(FIXTURE_PACKAGE_ID).unwrap(),
+    OBJECT_START_VERSION,
+    modules,
+    u64::MAX,
+    vec![],
+    BTreeMap::new(),
+  ).unwrap();
+  let package = Object::new_package_from_data(Data::Package(package), *transaction.digest());
+  let gas_after = Object::with_id_owner_version_for_testing(gas_id, lamport_version, sender);
+  let gas_owner = Owner::AddressOwner(sender);
+
+  let effects = effects(
+    *transaction.digest(),
+    vec![(gas_id, OBJECT_START_VERSION)],
+    vec![],
+    vec![(object_ref(&package), Owner::Immutable)],
+    vec![(object_ref(&gas_after), gas_owner)],
+    (object_ref(&gas_after), gas_owner),
+    None,
+  );
+
+  CheckpointTransaction {
+    transaction,
+    effects,
+    events: None,
+    input_objects: vec![gas],
+    output_objects: vec![package, gas_after],
+  }
+}
+
+/// Advances to the given epoch. The indexer reads the new epoch from the system state the transaction writes and
+/// from the SystemEpochInfoEvent it emits, which is why both are here even though nothing else looks at them.
+fn change_epoch_transaction(next_epoch: u64) -> CheckpointTransaction {
+  let transaction = VerifiedTransaction::new_change_epoch(
+    next_epoch,
+    ProtocolVersion::MAX,
+    0,
+    0,
+    0,
+    0,
+    1_700_000_000_000,
+    vec![],
+  ).into_inner();
+
+  let shared_version = OBJECT_START_VERSION;
+  let lamport_version = SequenceNumber::from_u64(shared_version.value() + 1);
+  let [wrapper_before, inner_before] = system_state_objects(next_epoch - 1, shared_version);
+  let [wrapper_after, inner_after] = system_state_objects(next_epoch, lamport_version);
+  let system_state_owner = Owner::Shared {initial_shared_version: OBJECT_START_VERSION};
+
+  let event = SystemEpochInfoEvent {
+    epoch: next_epoch - 1,
+    protocol_version: ProtocolVersion::MAX.as_u64(),
+    reference_gas_price: GAS_PRICE,
+    total_stake: 0,
+    storage_fund_reinvestment: 0,
+    storage_charge: 0,
+    storage_rebate: 0,
+    storage_fund_balance: 0,
+    stake_subsidy_amount: 0,
+    total_gas_fees: 0,
+    total_stake_rewards_distributed: 0,
+    leftover_storage_fund_inflow: 0,
+  };
+  let events = TransactionEvents {
+    data: vec![Event::new(
+      &ObjectID::from(SUI_SYSTEM_ADDRESS),
+      &Identifier::new("sui_system").unwrap(),
+      SuiAddress::ZERO,
+      StructTag::from_str("0x3::sui_system_state_inner::SystemEpochInfoEvent").unwrap(),
+      bcs::to_bytes(&event).unwrap(),
+    )],
+  };
+
+  // System transactions don't pay for gas
+  let gas_object = (
+    (ObjectID::ZERO, SequenceNumber::default(), ObjectDigest::MIN),
+    Owner::AddressOwner(SuiAddress::ZERO),
+  );
+  let inner_owner = Owner::ObjectOwner(SUI_SYSTEM_STATE_OBJECT_ID.into());
+  let effects = effects(
+    *transaction.digest(),
+    vec![(wrapper_before.id(), shared_version), (inner_before.id(), inner_before.version())],
+    vec![object_ref(&wrapper_before)],
+    vec![],
+    vec![(object_ref(&wrapper_after), system_state_owner), (object_ref(&inner_after), inner_owner)],
+    gas_object,
+    Some(&events),
+  );
+
+  CheckpointTransaction {
+    transaction,
+    effects,
+    events: Some(events),
+    input_objects: vec![wrapper_before, inner_before],
+    output_objects: vec![wrapper_after, inner_after],
+  }
+}
+
+/// The shared 0x5 object and the dynamic field that holds the actual state of the given epoch
+fn system_state_objects(epoch: u64, version: SequenceNumber) -> [Object; 2] {
+  let system_state_version = 1u64;
+  let wrapper = SuiSystemStateWrapper {
+    id: UID::new(SUI_SYSTEM_STATE_OBJECT_ID),
+    version: system_state_version,
+  };
+  let inner_id = derive_dynamic_field_id(
+    SUI_SYSTEM_STATE_OBJECT_ID,
+    &TypeTag::U64,
+    &bcs::to_bytes(&system_state_version).unwrap(),
+  ).unwrap();
+  let inner = Field {
+    id: UID::new(inner_id),
+    name: system_state_version,
+    value: system_state_inner(epoch),
+  };
+
+  let move_object = |type_: &str, contents: Vec<u8>| unsafe {
+    let type_ = StructTag::from_str(type_).unwrap().into();
+    MoveObject::new_from_execution_with_limit(type_, false, version, contents, u64::MAX).unwrap()
+  };
+  let wrapper = Object::new_move(
+    move_object("0x3::sui_system::SuiSystemState", bcs::to_bytes(&wrapper).unwrap()),
+    Owner::Shared {initial_shared_version: OBJECT_START_VERSION},
+    TransactionDigest::genesis_marker(),
+  );
+  let inner = Object::new_move(
+    move_object(
+      "0x2::dynamic_field::Field<u64, 0x3::sui_system_state_inner::SuiSystemStateInner>",
+      bcs::to_bytes(&inner).unwrap(),
+    ),
+    Owner::ObjectOwner(SUI_SYSTEM_STATE_OBJECT_ID.into()),
+    TransactionDigest::genesis_marker(),
+  );
+
+  [wrapper, inner]
+}
+
+/// A system state without any validator
+fn system_state_inner(epoch: u64) -> SuiSystemStateInnerV1 {
+  SuiSystemStateInnerV1 {
+    epoch,
+    protocol_version: ProtocolVersion::MAX.as_u64(),
+    system_state_version: 1,
+    validators: ValidatorSetV1 {
+      total_stake: 0,
+      active_validators: vec![],
+      pending_active_validators: TableVec::default(),
+      pending_removals: vec![],
+      staking_pool_mappings: Table::default(),
+      inactive_validators: Table::default(),
+      validator_candidates: Table::default(),
+      at_risk_validators: VecMap {contents: vec![]},
+      extra_fields: Bag::default(),
+    },
+    storage_fund: StorageFundV1 {
+      total_object_storage_rebates: Balance::new(0),
+      non_refundable_balance: Balance::new(0),
+    },
+    parameters: SystemParametersV1 {
+      epoch_duration_ms: 86_400_000,
+      stake_subsidy_start_epoch: 0,
+      max_validator_count: 150,
+      min_validator_joining_stake: 0,
+      validator_low_stake_threshold: 0,
+      validator_very_low_stake_threshold: 0,
+      validator_low_stake_grace_period: 0,
+      extra_fields: Bag::default(),
+    },
+    reference_gas_price: GAS_PRICE,
+    validator_report_records: VecMap {contents: vec![]},
+    stake_subsidy: StakeSubsidyV1 {
+      balance: Balance::new(0),
+      distribution_counter: 0,
+      current_distribution_amount: 0,
+      stake_subsidy_period_length: 0,
+      stake_subsidy_decrease_rate: 0,
+      extra_fields: Bag::default(),
+    },
+    safe_mode: false,
+    safe_mode_storage_rewards: Balance::new(0),
+    safe_mode_computation_rewards: Balance::new(0),
+    safe_mode_storage_rebates: 0,
+    safe_mode_non_refundable_storage_fee: 0,
+    epoch_start_timestamp_ms: 1_700_000_000_000,
+    extra_fields: Bag::default(),
+  }
+}
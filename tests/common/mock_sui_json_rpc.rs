@@ -0,0 +1,129 @@
+use std::{collections::{BTreeMap, HashMap}, convert::Infallible, net::SocketAddr, sync::Arc};
+use hyper::{
+  body::to_bytes, header::CONTENT_TYPE, service::{make_service_fn, service_fn}, Body, Request, Response, Server,
+};
+use serde_json::{json, Value};
+use sui_json_rpc_types::{Checkpoint, SuiGetPastObjectRequest, SuiPastObjectResponse, SuiTransactionBlockResponse};
+use sui_types::{
+  digests::TransactionDigest, full_checkpoint_content::{CheckpointData, CheckpointTransaction},
+};
+
+/// The JSON-RPC methods the JSON-RPC source calls
+const METHODS: [&str; 4] = [
+  "sui_getCheckpoint",
+  "sui_getLatestCheckpointSequenceNumber",
+  "sui_multiGetTransactionBlocks",
+  "sui_tryMultiGetPastObjects",
+];
+
+/// Stand-in for a JSON-RPC endpoint that serves a fixed set of checkpoints and their transactions but has pruned
+/// every object version, like a node that doesn't keep the history the checkpoints refer to. Transactions are
+/// served without their events.
+pub struct MockSuiJsonRpc {
+  checkpoints: BTreeMap<u64, CheckpointData>,
+  transactions: HashMap<TransactionDigest, CheckpointTransaction>,
+}
+
+impl MockSuiJsonRpc {
+  pub fn new(checkpoints: impl IntoIterator<Item = CheckpointData>) -> Self {
+    let checkpoints = checkpoints.into_iter()
+    .map(|checkpoint| (checkpoint.checkpoint_summary.sequence_number, checkpoint))
+    .collect::<BTreeMap<_, _>>();
+    let transactions = checkpoints.values()
+    .flat_map(|checkpoint| checkpoint.transactions.iter())
+    .map(|transaction| (*transaction.transaction.digest(), transaction.clone()))
+    .collect();
+
+    Self {checkpoints, transactions}
+  }
+
+  /// Starts serving on a random local port and returns the url to pass as --rpc-client-url
+  pub fn serve(self) -> String {
+    let mock = Arc::new(self);
+    let make_service = make_service_fn(move |_| {
+      let mock = Arc::clone(&mock);
+
+      async move {
+        Ok::<_, Infallible>(service_fn(move |request: Request<Body>| {
+          let mock = Arc::clone(&mock);
+          async move {Ok::<_, Infallible>(mock.respond(request).await)}
+        }))
+      }
+    });
+
+    let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+    let url = format!("http://{}", server.local_addr());
+    tokio::spawn(server);
+
+    url
+  }
+
+  async fn respond(&self, request: Request<Body>) -> Response<Body> {
+    let body = to_bytes(request.into_body()).await.unwrap();
+    let request = serde_json::from_slice::<Value>(&body).unwrap();
+    let params = &request["params"];
+
+    let result = match request["method"].as_str().unwrap() {
+      "rpc.discover" => Ok(json!({
+        "openrpc": "1.2.6",
+        "info": {"title": "Sui JSON-RPC", "version": "1.22.0"},
+        "methods": METHODS.map(|name| json!({"name": name, "params": []})),
+      })),
+      "sui_getLatestCheckpointSequenceNumber" => Ok(json!(self.checkpoints.keys().last().unwrap().to_string())),
+      "sui_getCheckpoint" => self.checkpoint(&params[0]),
+      "sui_multiGetTransactionBlocks" => Ok(self.transactions(&params[0])),
+      "sui_tryMultiGetPastObjects" => Ok(pruned_objects(&params[0])),
+      method => Err(format!("method {} is not supported", method)),
+    };
+
+    let response = match result {
+      Ok(result) => json!({"jsonrpc": "2.0", "id": request["id"], "result": result}),
+      Err(message) => json!({"jsonrpc": "2.0", "id": request["id"], "error": {"code": -32602, "message": message}}),
+    };
+
+    Response::builder()
+    .header(CONTENT_TYPE, "application/json")
+    .body(Body::from(response.to_string()))
+    .unwrap()
+  }
+
+  fn checkpoint(&self, id: &Value) -> Result<Value, String> {
+    let seq = id.as_str()
+    .and_then(|seq| seq.parse::<u64>().ok())
+    .ok_or_else(|| format!("invalid checkpoint {}", id))?;
+    let checkpoint = self.checkpoints.get(&seq).ok_or_else(|| format!("checkpoint {} does not exist", seq))?;
+    let summary = &checkpoint.checkpoint_summary;
+
+    Ok(serde_json::to_value(Checkpoint::from((
+      summary.data().clone(),
+      checkpoint.checkpoint_contents.clone(),
+      summary.auth_sig().signature.clone(),
+    ))).unwrap())
+  }
+
+  fn transactions(&self, digests: &Value) -> Value {
+    let digests = serde_json::from_value::<Vec<TransactionDigest>>(digests.clone()).unwrap();
+    let responses = digests.into_iter()
+    .map(|digest| {
+      let transaction = &self.transactions[&digest];
+
+      SuiTransactionBlockResponse {
+        raw_transaction: bcs::to_bytes(transaction.transaction.data()).unwrap(),
+        raw_effects: bcs::to_bytes(&transaction.effects).unwrap(),
+        ..SuiTransactionBlockResponse::new(digest)
+      }
+    })
+    .collect::<Vec<_>>();
+
+    serde_json::to_value(responses).unwrap()
+  }
+}
+
+fn pruned_objects(requests: &Value) -> Value {
+  let requests = serde_json::from_value::<Vec<SuiGetPastObjectRequest>>(requests.clone()).unwrap();
+  let responses = requests.into_iter()
+  .map(|request| SuiPastObjectResponse::VersionNotFound(request.object_id, request.version))
+  .collect::<Vec<_>>();
+
+  serde_json::to_value(responses).unwrap()
+}
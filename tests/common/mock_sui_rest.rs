@@ -0,0 +1,73 @@
+use std::{collections::BTreeMap, convert::Infallible, net::SocketAddr, sync::Arc};
+use hyper::{
+  header::CONTENT_TYPE, service::{make_service_fn, service_fn}, Body, Request, Response, Server, StatusCode,
+};
+use sui_types::full_checkpoint_content::CheckpointData;
+
+const APPLICATION_BCS: &str = "application/bcs";
+
+/// Stand-in for the two REST endpoints the fetchers use i.e. the latest checkpoint and a full checkpoint by
+/// sequence number. It serves a fixed set of checkpoints so tests neither need a sui-node nor any network.
+pub struct MockSuiRest {
+  checkpoints: BTreeMap<u64, CheckpointData>,
+}
+
+impl MockSuiRest {
+  pub fn new(checkpoints: impl IntoIterator<Item = CheckpointData>) -> Self {
+    let checkpoints = checkpoints.into_iter()
+    .map(|checkpoint| (checkpoint.checkpoint_summary.sequence_number, checkpoint))
+    .collect();
+
+    Self {checkpoints}
+  }
+
+  /// Starts serving on a random local port and returns the url to pass as --rpc-client-url
+  pub fn serve(self) -> String {
+    let mock = Arc::new(self);
+    let make_service = make_service_fn(move |_| {
+      let mock = Arc::clone(&mock);
+
+      async move {
+        Ok::<_, Infallible>(service_fn(move |request: Request<Body>| {
+          let mock = Arc::clone(&mock);
+          async move {Ok::<_, Infallible>(mock.respond(&request))}
+        }))
+      }
+    });
+
+    let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+    let url = format!("http://{}", server.local_addr());
+    tokio::spawn(server);
+
+    url
+  }
+
+  fn respond(&self, request: &Request<Body>) -> Response<Body> {
+    // Only what comes after /checkpoints matters so that it doesn't depend on how the client builds the prefix
+    let segments = request.uri().path().split('/').filter(|segment| !segment.is_empty()).collect::<Vec<_>>();
+    let Some(position) = segments.iter().position(|segment| *segment == "checkpoints") else {
+      return status(StatusCode::NOT_FOUND)
+    };
+
+    let bytes = match segments[position + 1..] {
+      [] => self.checkpoints.values().last().map(|checkpoint| bcs::to_bytes(&checkpoint.checkpoint_summary)),
+      [seq, "full"] => seq.parse::<u64>().ok()
+      .and_then(|seq| self.checkpoints.get(&seq))
+      .map(bcs::to_bytes),
+      _ => return status(StatusCode::NOT_FOUND),
+    };
+
+    match bytes {
+      Some(Ok(bytes)) => Response::builder()
+      .header(CONTENT_TYPE, APPLICATION_BCS)
+      .body(Body::from(bytes))
+      .unwrap(),
+      Some(Err(_)) => status(StatusCode::INTERNAL_SERVER_ERROR),
+      None => status(StatusCode::NOT_FOUND),
+    }
+  }
+}
+
+fn status(status: StatusCode) -> Response<Body> {
+  Response::builder().status(status).body(Body::empty()).unwrap()
+}
@@ -0,0 +1,3 @@
+pub mod fixtures;
+pub mod mock_sui_json_rpc;
+pub mod mock_sui_rest;
@@ -1,190 +1,300 @@
-use std::{marker::PhantomData, panic, sync::{mpsc::{sync_channel, SyncSender}, Arc, Mutex}};
+mod common;
+
+use std::{collections::HashSet, marker::PhantomData, ops::RangeInclusive, sync::{Arc, Mutex}, time::Duration};
+use async_trait::async_trait;
+use backoff::ExponentialBackoff;
 use eyre::{eyre, ensure, Result};
-use simple_home_dir::*;
-use tokio::spawn;
-use sui_sf_indexer::{args::Args, logger::Logger, process_manager::ProcessManager};
-use sysinfo::System;
+use mysten_metrics::metered_channel::channel;
+use prometheus::IntGauge;
+use prost::Message;
+use sf_sui_extractor::{
+  args::{Args, OutputFormat}, checkpoint_source::InMemorySource,
+  endpoints::{EndpointPool, RequestGate, RequestLimits}, fetcher::{download_checkpoint, RangeFetcher},
+  json_rpc_source::JsonRpcSource, logger::Logger, pb::sui::checkpoint as pb,
+  process_manager::{ProcessManager, Shutdown},
+};
+use tokio::time::timeout;
+use common::{
+  fixtures::{fixture_checkpoints, fixture_checkpoints_with_activity}, mock_sui_json_rpc::MockSuiJsonRpc,
+  mock_sui_rest::MockSuiRest,
+};
 
+/// Each state is named after the line that was seen last
+struct StartState;
 struct InitState;
 struct BlockStartState;
-struct BlockEndState;
 struct CheckpointState;
 struct TransactionState;
+struct TxIndexState;
 struct ObjectChangeState;
 struct EventState;
 struct DisplayUpdateState;
+struct PackageState;
+struct EpochState;
+struct BlockEndState;
 
-trait LineValidation {
-  fn validate(&self, line: &str) -> Result<Box<dyn LineValidation>>;
-  fn full_cycle(&self) -> bool {false}
+/// Lines that may follow in the order print_checkpoint_lines emits them
+trait LineState {
+  const NEXT: &'static [&'static str];
+  const FULL_CYCLE: bool = false;
 }
 
-struct Test <State = InitState> {
-  state: PhantomData<State>,
+impl LineState for StartState {
+  const NEXT: &'static [&'static str] = &["INIT"];
 }
 
-struct TestLogger {
-  tx: SyncSender<String>,
+impl LineState for InitState {
+  const NEXT: &'static [&'static str] = &["BLOCK_START"];
 }
 
-impl Logger for TestLogger {
-  fn log(&self, msg: &str) {
-    let _ = self.tx.send(msg.to_string());
-  }
+impl LineState for BlockStartState {
+  const NEXT: &'static [&'static str] = &["CHECKPOINT"];
 }
 
-impl LineValidation for Test<InitState> {
-  fn validate(&self, line: &str) -> Result<Box<dyn LineValidation>> {
-    ensure!(line.eq("INIT"), "expected INIT");
-    Ok(Box::new(Test::<BlockStartState> {state: PhantomData}))
-  }
+impl LineState for CheckpointState {
+  const NEXT: &'static [&'static str] = &["TRX", "TX_INDEX", "OBJ_CHANGE"];
 }
 
-impl LineValidation for Test<BlockStartState> {
-  fn validate(&self, line: &str) -> Result<Box<dyn LineValidation>> {
-    ensure!(line.eq("BLOCK_START"), "expected BLOCK_START");
-    Ok(Box::new(Test::<CheckpointState> {state: PhantomData}))
-  }
+impl LineState for TransactionState {
+  const NEXT: &'static [&'static str] = &["TRX", "TX_INDEX", "OBJ_CHANGE"];
 }
 
-impl LineValidation for Test<CheckpointState> {
-  fn validate(&self, line: &str) -> Result<Box<dyn LineValidation>> {
-    ensure!(line.eq("CHECKPOINT"), "expected CHECKPOINT");
-    Ok(Box::new(Test::<TransactionState> {state: PhantomData}))
-  }
+impl LineState for TxIndexState {
+  const NEXT: &'static [&'static str] = &["TX_INDEX", "OBJ_CHANGE"];
 }
 
-impl LineValidation for Test<TransactionState> {
-  fn validate(&self, line: &str) -> Result<Box<dyn LineValidation>> {
-    if line.eq("TRX") {
-      Ok(Box::new(Test::<TransactionState> {state: PhantomData}))
-    } else if line.eq("OBJ_CHANGE") {
-      Ok(Box::new(Test::<ObjectChangeState> {state: PhantomData}))
-    } else if line.eq("EVT") {
-      Ok(Box::new(Test::<EventState> {state: PhantomData}))
-    } else if line.eq("DSP_UPDATE") {
-      Ok(Box::new(Test::<DisplayUpdateState> {state: PhantomData}))
-    } else if line.eq("BLOCK_END") {
-      Ok(Box::new(Test::<BlockEndState> {state: PhantomData}))
-    } else {
-      Err(eyre!("expected either TRX or BLOCK_END"))
-    }
-  }
+impl LineState for ObjectChangeState {
+  const NEXT: &'static [&'static str] = &["EVT", "DSP_UPDATE", "PACKAGE", "EPOCH", "BLOCK_END"];
 }
 
-impl LineValidation for Test<ObjectChangeState> {
-  fn validate(&self, line: &str) -> Result<Box<dyn LineValidation>> {
-    if line.eq("OBJ_CHANGE") {
-      Ok(Box::new(Test::<ObjectChangeState> {state: PhantomData}))
-    } else if line.eq("EVT") {
-      Ok(Box::new(Test::<EventState> {state: PhantomData}))
-    } else if line.eq("DSP_UPDATE") {
-      Ok(Box::new(Test::<DisplayUpdateState> {state: PhantomData}))
-    } else if line.eq("BLOCK_END") {
-      Ok(Box::new(Test::<BlockEndState> {state: PhantomData}))
-    } else {
-      Err(eyre!("expected either OBJ_CHANGE, EVT, DSP_UPDATE or BLOCK_END"))
-    }
-  }
+impl LineState for EventState {
+  const NEXT: &'static [&'static str] = &["EVT", "DSP_UPDATE", "PACKAGE", "EPOCH", "BLOCK_END"];
 }
 
-impl LineValidation for Test<EventState> {
-  fn validate(&self, line: &str) -> Result<Box<dyn LineValidation>> {
-    if line.eq("EVT") {
-      Ok(Box::new(Test::<EventState> {state: PhantomData}))
-    } else if line.eq("DSP_UPDATE") {
-      Ok(Box::new(Test::<DisplayUpdateState> {state: PhantomData}))
-    } else if line.eq("BLOCK_END") {
-      Ok(Box::new(Test::<BlockEndState> {state: PhantomData}))
-    } else {
-      Err(eyre!("expected either EVT, DSP_UPDATE or BLOCK_END"))
-    }
-  }
+impl LineState for DisplayUpdateState {
+  const NEXT: &'static [&'static str] = &["DSP_UPDATE", "PACKAGE", "EPOCH", "BLOCK_END"];
 }
 
-impl LineValidation for Test<DisplayUpdateState> {
-  fn validate(&self, line: &str) -> Result<Box<dyn LineValidation>> {
-    if line.eq("BLOCK_START") {
-      Ok(Box::new(Test::<BlockStartState> {state: PhantomData}))
-    } else {
-      Err(eyre!("expected BLOCK_START"))
-    }
-  }
+impl LineState for PackageState {
+  const NEXT: &'static [&'static str] = &["PACKAGE", "EPOCH", "BLOCK_END"];
+}
+
+impl LineState for EpochState {
+  const NEXT: &'static [&'static str] = &["BLOCK_END"];
 }
 
-impl LineValidation for Test<BlockEndState> {
+impl LineState for BlockEndState {
+  const NEXT: &'static [&'static str] = &["BLOCK_START"];
+  const FULL_CYCLE: bool = true;
+}
+
+trait LineValidation {
+  fn validate(&self, line: &str) -> Result<Box<dyn LineValidation>>;
+  fn full_cycle(&self) -> bool {false}
+}
+
+struct Test <S = StartState> {
+  state: PhantomData<S>,
+}
+
+fn test<S: LineState + 'static>() -> Box<dyn LineValidation> {
+  Box::new(Test::<S> {state: PhantomData})
+}
+
+impl<S: LineState + 'static> LineValidation for Test<S> {
   fn validate(&self, line: &str) -> Result<Box<dyn LineValidation>> {
-    ensure!(line.eq("BLOCK_START"), "expected BLOCK_START");
-    Ok(Box::new(Test::<CheckpointState> {state: PhantomData}))
+    ensure!(S::NEXT.contains(&line), "expected one of {}", S::NEXT.join(", "));
+
+    let next = match line {
+      "INIT" => test::<InitState>(),
+      "BLOCK_START" => test::<BlockStartState>(),
+      "CHECKPOINT" => test::<CheckpointState>(),
+      "TRX" => test::<TransactionState>(),
+      "TX_INDEX" => test::<TxIndexState>(),
+      "OBJ_CHANGE" => test::<ObjectChangeState>(),
+      "EVT" => test::<EventState>(),
+      "DSP_UPDATE" => test::<DisplayUpdateState>(),
+      "PACKAGE" => test::<PackageState>(),
+      "EPOCH" => test::<EpochState>(),
+      "BLOCK_END" => test::<BlockEndState>(),
+      _ => return Err(eyre!("unknown line {}", line)),
+    };
+
+    Ok(next)
   }
 
-  fn full_cycle(&self) -> bool {true}
+  fn full_cycle(&self) -> bool {S::FULL_CYCLE}
 }
 
+struct TestLogger {
+  lines: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait]
+impl Logger for TestLogger {
+  async fn log(&mut self, msg: &str) -> Result<()> {
+    self.lines.lock().unwrap().push(msg.to_string());
+    Ok(())
+  }
+}
+
+const FIXTURE_CHECKPOINTS: RangeInclusive<u64> = 1..=3;
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
 async fn test_raw_data_printed_in_stdout() {
-  let cycles = Arc::new(Mutex::new(0));
-  let cycles_clone = cycles.clone();
+  let url = MockSuiRest::new(fixture_checkpoints_with_activity(*FIXTURE_CHECKPOINTS.start())).serve();
+
+  let args = Args {
+    chain_id: "4btiuiMPvEENsttpZC7CZ53DruC3MAgfznDbASZ7DR6S".to_string(),
+    starting_checkpoint_seq: Some(*FIXTURE_CHECKPOINTS.start()),
+    stop_checkpoint: Some(*FIXTURE_CHECKPOINTS.end()),
+    rpc_client_urls: vec![url],
+    ..Default::default()
+  };
+
+  let pm = ProcessManager::new(args);
+  let lines = Arc::new(Mutex::new(vec![]));
+  let test_logger = TestLogger {lines: lines.clone()};
+
+  assert_eq!(pm.run(test_logger).await, Shutdown::Finished);
 
-  panic::set_hook(Box::new(move |_| {
-    let lock = cycles_clone.lock().unwrap();
-    assert_eq!(*lock, 2);
+  let mut test = test::<StartState>();
+  let mut cycles = 0;
+  let mut seen = HashSet::new();
 
-    println!(">>>>>>>>>>>>>>> Test Passes <<<<<<<<<<<<<<<<");
-    std::process::exit(0);
-  }));
+  for line in lines.lock().unwrap().iter() {
+    let parts = line.split(" ").collect::<Vec<_>>();
+    let result = test.validate(&parts[1]);
+    assert!(result.is_ok(), "unexpected line {}: {:?}", line, result.err());
+    test = result.unwrap();
+    seen.insert(parts[1].to_string());
 
-  fn sui_config_path() -> String {
-    format!("{}/.sf_sui/sui_config/full_node.yaml", home_dir().unwrap().display().to_string())
+    if test.full_cycle() {
+      cycles += 1;
+    }
   }
 
+  assert_eq!(cycles, FIXTURE_CHECKPOINTS.count());
+  // The fixture publishes a package and closes the epoch so every kind of line but events and display updates
+  // must have been emitted
+  for line in ["TRX", "TX_INDEX", "OBJ_CHANGE", "PACKAGE", "EPOCH"] {
+    assert!(seen.contains(line), "no {} line was emitted", line);
+  }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_block_lines_printed_in_stdout() {
+  let checkpoints = fixture_checkpoints(*FIXTURE_CHECKPOINTS.start(), *FIXTURE_CHECKPOINTS.end());
+  let expected_lines = checkpoints.iter().map(|checkpoint| {
+    let summary = &checkpoint.checkpoint_summary;
+    // The first fixture checkpoint has no parent
+    let parent_digest = summary.previous_digest
+    .map(|digest| digest.base58_encode())
+    .unwrap_or_else(|| "1".repeat(32));
+
+    format!(
+      "\nFIRE BLOCK {} {} {} {} {} {}",
+      summary.sequence_number,
+      summary.digest().base58_encode(),
+      summary.sequence_number - 1,
+      parent_digest,
+      summary.sequence_number,
+      summary.timestamp_ms * 1_000_000,
+    )
+  })
+  .collect::<Vec<_>>();
+  let url = MockSuiRest::new(checkpoints).serve();
+
   let args = Args {
-    sui_node_config: sui_config_path(),
     chain_id: "4btiuiMPvEENsttpZC7CZ53DruC3MAgfznDbASZ7DR6S".to_string(),
-    starting_checkpoint_seq: 1948619,
-    rpc_client_url: None
+    starting_checkpoint_seq: Some(*FIXTURE_CHECKPOINTS.start()),
+    stop_checkpoint: Some(*FIXTURE_CHECKPOINTS.end()),
+    rpc_client_urls: vec![url],
+    output_format: OutputFormat::Block,
+    ..Default::default()
   };
-  
+
   let pm = ProcessManager::new(args);
+  let lines = Arc::new(Mutex::new(vec![]));
+  let test_logger = TestLogger {lines: lines.clone()};
 
-  let (tx, rx) = sync_channel::<String>(10);
-  let test_logger = TestLogger {tx};
-
-  let cycles_clone = cycles.clone();
-  spawn(async move {
-    let mut test: Box<dyn LineValidation> = Box::new(Test::<InitState>{state: PhantomData});
-
-    while let Ok(line) = rx.recv() {
-      let parts = line.split(" ").collect::<Vec<_>>();
-      // First line must start with FIRE BLOCK_START
-      // Followed by FIRE CHECKPOINT
-      // Followed by 0 or more FIRE TRX
-      // Followed by 0 or more FIRE OBJ_CHANGE
-      // Followed by 0 or more FIRE EVT
-      // Followed by 0 or more FIRE DSP_UPDATE
-      // Followed by FIRE BLOCK_END
-      let result = test.validate(&parts[1]);
-      assert!(result.is_ok());
-      test = result.unwrap();
-
-      if test.full_cycle() {
-        let mut lock = cycles_clone.lock().unwrap();
-        *lock += 1;
-        println!("Full cycle {lock:?}");
-
-        if *lock == 2 {
-          let sys = System::new_all();
-          for (_, process) in sys.processes() {
-            if process.name().eq("sui-node") {
-              process.kill();
-            }
-          }
-
-          break;     
-        }
-      }
-    }
-  });
+  assert_eq!(pm.run(test_logger).await, Shutdown::Finished);
+
+  let lines = lines.lock().unwrap();
+  assert_eq!(lines[0], "\nFIRE INIT 3.0 sui.checkpoint.v1.CheckpointData");
+  assert_eq!(lines.len(), expected_lines.len() + 1);
+
+  for (line, expected_line) in lines[1..].iter().zip(expected_lines) {
+    let (fields, payload) = line.rsplit_once(' ').unwrap();
+    assert_eq!(fields, expected_line);
+
+    let block = pb::CheckpointData::decode(base64::decode(payload).unwrap().as_slice()).unwrap();
+    assert_eq!(block.checkpoint.unwrap().digest, expected_line.split(' ').nth(3).unwrap());
+  }
+}
+
+#[tokio::test]
+async fn test_mock_serves_fixture_checkpoints() {
+  let url = MockSuiRest::new(fixture_checkpoints(1, 2)).serve();
+  let endpoints = EndpointPool::new(&[url], RequestLimits::default()).unwrap();
+  let backoff = ExponentialBackoff {
+    max_elapsed_time: Some(Duration::from_secs(5)),
+    ..Default::default()
+  };
+
+  let checkpoint_download_data = download_checkpoint(&endpoints, 2, backoff.clone()).await.unwrap();
+  assert_eq!(checkpoint_download_data.data.checkpoint_summary.sequence_number, 2);
+
+  assert!(download_checkpoint(&endpoints, 3, backoff).await.is_err());
+}
+
+#[tokio::test]
+async fn test_range_fetcher_reads_any_source_in_order() {
+  let source = Arc::new(InMemorySource::new(fixture_checkpoints(1, 5)));
+  let (sender, mut receiver) = channel(10, &IntGauge::new("test_download_queue", "test").unwrap());
+
+  RangeFetcher::new(source, vec![2..=4], None, sender).run().await.unwrap();
+
+  let mut seqs = vec![];
+  while let Some(checkpoint_download_data) = receiver.recv().await {
+    seqs.push(checkpoint_download_data.data.checkpoint_summary.sequence_number);
+  }
+
+  assert_eq!(seqs, vec![2, 3, 4]);
+}
+
+#[tokio::test]
+async fn test_range_fetcher_gives_up_on_permanent_failures() {
+  // Checkpoint 6 will never be in the source so retrying it forever would hang the test
+  let source = Arc::new(InMemorySource::new(fixture_checkpoints(1, 5)));
+  let (sender, mut receiver) = channel(10, &IntGauge::new("test_download_queue", "test").unwrap());
+
+  assert!(RangeFetcher::new(source, vec![4..=6], None, sender).run().await.is_err());
+
+  let mut seqs = vec![];
+  while let Some(checkpoint_download_data) = receiver.recv().await {
+    seqs.push(checkpoint_download_data.data.checkpoint_summary.sequence_number);
+  }
+
+  assert_eq!(seqs, vec![4, 5]);
+}
+
+#[tokio::test]
+async fn test_json_rpc_source_gives_up_on_pruned_objects() {
+  let url = MockSuiJsonRpc::new(fixture_checkpoints_with_activity(1)).serve();
+  let request_gate = RequestGate::new(RequestLimits::default()).unwrap();
+  let source = JsonRpcSource::connect(&[url], request_gate).await.unwrap();
+  let backoff = ExponentialBackoff {
+    max_elapsed_time: None,
+    ..Default::default()
+  };
+
+  // Checkpoint 1 has no transaction so it doesn't need any object
+  let checkpoint_download_data = download_checkpoint(&source, 1, backoff.clone()).await.unwrap();
+  assert_eq!(checkpoint_download_data.data.checkpoint_summary.sequence_number, 1);
 
-  pm.start(test_logger).await;
+  // The publish in checkpoint 2 needs its gas object which the mock has pruned. Retrying it would never end.
+  let result = timeout(Duration::from_secs(10), download_checkpoint(&source, 2, backoff)).await.unwrap();
+  let err = result.unwrap_err();
+  assert!(err.to_string().contains("does not have object"), "{:?}", err);
 }